@@ -0,0 +1,18 @@
+//! Sets `cfg(input = "dayN")` for every personal puzzle input in `res/`, so
+//! tests on inputs that are not checked in are reported as ignored.
+
+fn main() {
+    println!("cargo::rerun-if-changed=res");
+    let days = (1..=25)
+        .map(|day| format!("\"day{day}\""))
+        .collect::<Vec<_>>();
+    println!(
+        "cargo::rustc-check-cfg=cfg(input, values({}))",
+        days.join(", ")
+    );
+    for day in 1..=25 {
+        if std::path::Path::new(&format!("res/day{day}/input.txt")).exists() {
+            println!("cargo::rustc-cfg=input=\"day{day}\"");
+        }
+    }
+}
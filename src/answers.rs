@@ -0,0 +1,271 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::solution::{self, Parsed};
+
+/// Known answers for puzzle inputs, as listed in `answers.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default, rename = "input")]
+    pub inputs: Vec<Entry>,
+}
+
+/// The answers for one input file, relative to the `res` directory
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    pub day: usize,
+    pub file: PathBuf,
+    pub part1: Option<Answer>,
+    pub part2: Option<Answer>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Answer {
+    pub expected: Option<String>,
+    /// Answers that were rejected before
+    #[serde(default)]
+    pub wrong: Vec<String>,
+    /// An answer that was rejected as too low, so the right one is above it
+    pub too_low: Option<String>,
+}
+
+impl Answer {
+    /// Checks `answer`, returning why it is not accepted
+    pub fn check(&self, answer: &str) -> Result<(), String> {
+        if self.wrong.iter().any(|wrong| wrong == answer) {
+            return Err(format!("{answer} is a known wrong answer"));
+        }
+        if let Some(too_low) = &self.too_low {
+            let (Ok(low), Ok(number)) = (too_low.parse::<i128>(), answer.parse::<i128>()) else {
+                return Err(format!("{answer} cannot be compared to {too_low}"));
+            };
+            if number <= low {
+                return Err(format!("{answer} is too low, {too_low} already was"));
+            }
+        }
+        match &self.expected {
+            Some(expected) if expected != answer => {
+                Err(format!("expected {expected}, found {answer}"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    /// The input file does not exist
+    Skipped,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub day: usize,
+    pub part: u8,
+    pub file: PathBuf,
+    pub outcome: Outcome,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (day, part, file) = (self.day, self.part, self.file.display());
+        write!(f, "day {day} part {part} ({file}): ")?;
+        match &self.outcome {
+            Outcome::Passed => write!(f, "ok"),
+            Outcome::Skipped => write!(f, "skipped, no input"),
+            Outcome::Failed(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl Manifest {
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    pub fn for_day(&self, day: usize) -> impl Iterator<Item = &Entry> {
+        self.inputs.iter().filter(move |entry| entry.day == day)
+    }
+
+    /// Runs the registered solvers on every listed input below `res`
+    ///
+    /// Inputs of days without a solver are reported as failed.
+    pub fn check(&self, res: &Path) -> Vec<Report> {
+        let mut reports = vec![];
+        for entry in &self.inputs {
+            if solution::get(entry.day).is_none() {
+                let reason = format!("no solver for day {}", entry.day);
+                reports.extend(entry.reports(|_| Outcome::Failed(reason.clone())));
+            }
+        }
+        for day in solution::DAYS {
+            for entry in self.for_day(day.day) {
+                let text = match std::fs::read_to_string(res.join(&entry.file)) {
+                    Ok(text) => text,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        reports.extend(entry.reports(|_| Outcome::Skipped));
+                        continue;
+                    }
+                    Err(e) => {
+                        reports.extend(entry.reports(|_| Outcome::Failed(e.to_string())));
+                        continue;
+                    }
+                };
+                match day.solver.parse(&text) {
+                    Ok(input) => reports.extend(entry.reports(|part| entry.run(&*input, part))),
+                    Err(e) => reports.extend(entry.reports(|_| Outcome::Failed(e.to_string()))),
+                }
+            }
+        }
+        reports
+    }
+}
+
+impl Entry {
+    fn answer(&self, part: u8) -> Option<&Answer> {
+        match part {
+            1 => self.part1.as_ref(),
+            _ => self.part2.as_ref(),
+        }
+    }
+
+    fn reports<'a>(
+        &'a self,
+        mut outcome: impl FnMut(u8) -> Outcome + 'a,
+    ) -> impl Iterator<Item = Report> + 'a {
+        [1, 2]
+            .into_iter()
+            .filter(|&part| self.answer(part).is_some())
+            .map(move |part| Report {
+                day: self.day,
+                part,
+                file: self.file.clone(),
+                outcome: outcome(part),
+            })
+    }
+
+    fn run(&self, input: &dyn Parsed, part: u8) -> Outcome {
+        let answer = match part {
+            1 => input.part1(),
+            _ => input.part2(),
+        };
+        let checked = answer
+            .map_err(|e| e.to_string())
+            .and_then(|answer| self.answer(part).unwrap().check(&answer));
+        match checked {
+            Ok(()) => Outcome::Passed,
+            Err(reason) => Outcome::Failed(reason),
+        }
+    }
+}
+
+/// The expected answer to `part` of `res/dayN/input.txt` in `answers.toml`
+///
+/// Lets the tests on personal inputs share the manifest's answers.
+#[cfg(test)]
+pub(crate) fn expected(day: usize, part: u8) -> String {
+    let manifest = Manifest::parse(include_str!("../answers.toml")).unwrap();
+    let file = PathBuf::from(format!("day{day}/input.txt"));
+    let expected = manifest
+        .for_day(day)
+        .find(|entry| entry.file == file)
+        .and_then(|entry| entry.answer(part)?.expected.clone());
+    expected.unwrap_or_else(|| panic!("answers.toml has no answer to day {day} part {part}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{Answer, Manifest, Outcome};
+
+    #[test]
+    fn manifest() {
+        let manifest = Manifest::parse(include_str!("../answers.toml")).unwrap();
+        let reports = manifest.check(Path::new("res"));
+        let failed = reports
+            .iter()
+            .filter(|r| matches!(r.outcome, Outcome::Failed(_)))
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>();
+        assert!(failed.is_empty(), "{}", failed.join("\n"));
+    }
+
+    #[test]
+    fn expected() {
+        assert_eq!(super::expected(18, 1), "42317");
+        assert_eq!(super::expected(8, 2), "12927600769609");
+    }
+
+    #[test]
+    fn known_wrong() {
+        let answer = Answer {
+            expected: Some("42317".into()),
+            wrong: vec!["42590".into()],
+            too_low: None,
+        };
+        assert_eq!(answer.check("42317"), Ok(()));
+        assert_eq!(
+            answer.check("42590"),
+            Err("42590 is a known wrong answer".into())
+        );
+        assert_eq!(answer.check("1"), Err("expected 42317, found 1".into()));
+
+        let unknown = Answer {
+            expected: None,
+            wrong: vec!["54953".into()],
+            too_low: None,
+        };
+        assert_eq!(unknown.check("54925"), Ok(()));
+    }
+
+    #[test]
+    fn too_low() {
+        let answer = Answer {
+            expected: None,
+            wrong: vec![],
+            too_low: Some("6930".into()),
+        };
+        assert_eq!(answer.check("8098"), Ok(()));
+        assert_eq!(
+            answer.check("6930"),
+            Err("6930 is too low, 6930 already was".into())
+        );
+        assert_eq!(
+            answer.check("-1"),
+            Err("-1 is too low, 6930 already was".into())
+        );
+        assert_eq!(
+            answer.check("x"),
+            Err("x cannot be compared to 6930".into())
+        );
+    }
+
+    #[test]
+    fn missing_input() {
+        let text = "[[input]]\nday = 6\nfile = \"day6/missing.txt\"\npart2 = {}\n\n\
+                    [[input]]\nday = 99\nfile = \"day99/input.txt\"\npart1 = {}\n";
+        let reports = Manifest::parse(text).unwrap().check(Path::new("res"));
+        let outcomes = reports
+            .iter()
+            .map(|r| (r.day, r.part, r.outcome.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            [
+                (99, 1, Outcome::Failed("no solver for day 99".into())),
+                (6, 2, Outcome::Skipped),
+            ]
+        );
+        assert!(Manifest::parse("[[input]]\nday = 1\n").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    fmt::Write,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Result, solution::Day};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// Untimed runs before the measurement
+    pub warmup: usize,
+    /// Timed runs
+    pub runs: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            warmup: 3,
+            runs: 10,
+        }
+    }
+}
+
+/// Timings of one stage of a day: parsing, a part, or a variant of a part
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub day: usize,
+    pub stage: String,
+    pub runs: usize,
+    pub min_ns: u64,
+    pub median_ns: u64,
+    pub mean_ns: u64,
+}
+
+impl Measurement {
+    fn of(day: usize, stage: &str, mut times: Vec<Duration>) -> Self {
+        times.sort();
+        let nanos = |d: Duration| d.as_nanos() as u64;
+        let total = times.iter().sum::<Duration>();
+        Self {
+            day,
+            stage: stage.to_owned(),
+            runs: times.len(),
+            min_ns: times.first().copied().map_or(0, nanos),
+            median_ns: times.get(times.len() / 2).copied().map_or(0, nanos),
+            mean_ns: nanos(total) / times.len().max(1) as u64,
+        }
+    }
+}
+
+/// Runs `f` `config.warmup` times, then times it `config.runs` times
+pub fn time<T>(config: &Config, mut f: impl FnMut() -> T) -> Vec<Duration> {
+    for _ in 0..config.warmup {
+        black_box(f());
+    }
+    (0..config.runs.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .collect()
+}
+
+/// Times parsing, both parts and all variants of `day` on `text`
+///
+/// Each stage is run once up front, so a failing input is reported instead of timed.
+pub fn bench_day(day: &Day, text: &str, config: &Config) -> Result<Vec<Measurement>> {
+    let input = day.solver.parse(text)?;
+    input.part1()?;
+    input.part2()?;
+    let variants = input.variants();
+    for (_, f) in &variants {
+        f()?;
+    }
+
+    let mut results = vec![
+        Measurement::of(day.day, "parse", time(config, || day.solver.parse(text))),
+        Measurement::of(day.day, "part1", time(config, || input.part1())),
+        Measurement::of(day.day, "part2", time(config, || input.part2())),
+    ];
+    for (name, f) in &variants {
+        results.push(Measurement::of(day.day, name, time(config, f)));
+    }
+    Ok(results)
+}
+
+fn format_ns(ns: u64) -> String {
+    match ns {
+        0..=999 => format!("{ns} ns"),
+        1_000..=999_999 => format!("{:.1} µs", ns as f64 / 1e3),
+        1_000_000..=999_999_999 => format!("{:.1} ms", ns as f64 / 1e6),
+        _ => format!("{:.2} s", ns as f64 / 1e9),
+    }
+}
+
+/// Renders the results as a table
+///
+/// With a `baseline`, the last column shows the change of the median per stage.
+pub fn table(results: &[Measurement], baseline: Option<&[Measurement]>) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "{:>3}  {:<20} {:>10} {:>10} {:>10}",
+        "day", "stage", "min", "median", "mean"
+    );
+    if baseline.is_some() {
+        let _ = write!(out, " {:>8}", "change");
+    }
+    out.push('\n');
+    for m in results {
+        let _ = write!(
+            out,
+            "{:>3}  {:<20} {:>10} {:>10} {:>10}",
+            m.day,
+            m.stage,
+            format_ns(m.min_ns),
+            format_ns(m.median_ns),
+            format_ns(m.mean_ns)
+        );
+        let base = baseline
+            .into_iter()
+            .flatten()
+            .find(|b| b.day == m.day && b.stage == m.stage);
+        if let Some(base) = base {
+            let change = (m.median_ns as f64 / base.median_ns.max(1) as f64 - 1.0) * 100.0;
+            let _ = write!(out, " {change:>+7.1}%");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn to_json(results: &[Measurement]) -> String {
+    serde_json::to_string_pretty(results).expect("measurements are serializable")
+}
+
+pub fn from_json(json: &str) -> serde_json::Result<Vec<Measurement>> {
+    serde_json::from_str(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{bench_day, from_json, table, to_json, Config, Measurement};
+    use crate::solution;
+
+    #[test]
+    fn statistics() {
+        let times = [5, 1, 3, 2, 100].map(Duration::from_nanos).to_vec();
+        let m = Measurement::of(1, "part1", times);
+        assert_eq!((m.runs, m.min_ns, m.median_ns, m.mean_ns), (5, 1, 3, 22));
+    }
+
+    #[test]
+    fn day5_variants() {
+        let config = Config { warmup: 0, runs: 2 };
+        let text = "seeds: 79 14 55 13\n\nseed-to-location map:\n50 98 2\n52 50 48\n";
+        let results = bench_day(solution::get(5).unwrap(), text, &config).unwrap();
+        let stages = results.iter().map(|m| m.stage.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            stages,
+            [
+                "parse",
+                "part1",
+                "part2",
+                "part2 (range lists)",
+                "part2 (every seed)"
+            ]
+        );
+        assert!(results.iter().all(|m| m.runs == 2));
+
+        assert_eq!(from_json(&to_json(&results)).unwrap(), results);
+        let rendered = table(&results, Some(&results));
+        assert_eq!(rendered.lines().count(), 6);
+        assert!(rendered.lines().nth(5).unwrap().ends_with("+0.0%"));
+    }
+}
@@ -1,28 +1,384 @@
 use std::{
+    collections::VecDeque,
     fs::File,
     io::{self, BufRead, BufReader},
     path::Path,
 };
 
+use log::trace;
 use regex::Regex;
 
-pub fn main(path: &Path) -> io::Result<usize> {
-    let mut reader = BufReader::new(File::open(path)?);
+use crate::{
+    error::{ParseError, Result},
+    solution::{Solution, Variant},
+};
+
+const DAY: usize = 1;
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    type Input = Vec<String>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        Ok(text.lines().map(str::to_owned).collect())
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        Ok(decode_lines(input, &Vocabulary::digits())?.total)
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        Ok(decode_lines(input, &Vocabulary::english())?.total)
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![("part2 (regex)", |input| {
+            calibrate_lines(input, true).map(|total| total.to_string())
+        })]
+    }
+}
+
+/// Spellings of digits, which count like `0`-`9`
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    words: Vec<(String, usize)>,
+}
+
+impl Vocabulary {
+    pub fn new<S: Into<String>>(words: impl IntoIterator<Item = (S, usize)>) -> Self {
+        let words = words.into_iter().map(|(word, digit)| (word.into(), digit));
+        Self {
+            words: words.collect(),
+        }
+    }
+
+    /// Only `0`-`9`
+    pub fn digits() -> Self {
+        Self::default()
+    }
+
+    pub fn english() -> Self {
+        Self::numbered([
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ])
+    }
+
+    pub fn german() -> Self {
+        Self::numbered([
+            "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+        ])
+    }
+
+    pub fn french() -> Self {
+        Self::numbered([
+            "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf",
+        ])
+    }
+
+    pub fn roman() -> Self {
+        Self::numbered(["I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX"])
+    }
+
+    pub fn scanner(&self) -> Scanner {
+        Scanner::new(self)
+    }
+
+    /// Words for 1 to 9
+    fn numbered(words: [&str; 9]) -> Self {
+        Self::new(words.into_iter().zip(1..))
+    }
+
+    /// The longest digit or word starting at byte `i` of `line`
+    fn starting_at(&self, line: &str, i: usize) -> Option<usize> {
+        let rest = line.get(i..)?;
+        self.longest(rest.as_bytes()[0], |word| rest.starts_with(word))
+    }
+
+    /// The longest digit or word ending at byte `i` of `line`
+    fn ending_at(&self, line: &str, i: usize) -> Option<usize> {
+        let before = line.get(..i)?;
+        self.longest(before.as_bytes()[i - 1], |word| before.ends_with(word))
+    }
+
+    fn longest(&self, byte: u8, matches: impl Fn(&str) -> bool) -> Option<usize> {
+        if byte.is_ascii_digit() {
+            return Some((byte - b'0') as usize);
+        }
+        self.words
+            .iter()
+            .filter(|(word, _)| matches(word))
+            .max_by_key(|(word, _)| word.len())
+            .map(|(_, digit)| *digit)
+    }
+
+    /// The first digit of `line`; of overlapping words, the one starting first
+    pub fn first(&self, line: &str) -> Option<usize> {
+        (0..line.len()).find_map(|i| self.starting_at(line, i))
+    }
+
+    /// The last digit of `line`; of overlapping words, the one ending last
+    ///
+    /// In "eightwo", this is the 2, while [`Vocabulary::first`] finds the 8.
+    pub fn last(&self, line: &str) -> Option<usize> {
+        (1..=line.len()).rev().find_map(|i| self.ending_at(line, i))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    pub first: usize,
+    pub last: usize,
+}
+
+impl Calibration {
+    pub fn value(&self) -> usize {
+        self.first * 10 + self.last
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    /// The digits of every non-empty line
+    pub lines: Vec<Calibration>,
+    pub total: usize,
+}
+
+/// Finds the first and last digit of every line, spelled out with `vocabulary`
+pub fn decode(reader: impl BufRead, vocabulary: &Vocabulary) -> Result<Decoded> {
+    vocabulary.scanner().decode(reader)
+}
+
+/// Same as [`decode`], on lines that are already read
+pub fn decode_lines(lines: &[String], vocabulary: &Vocabulary) -> Result<Decoded> {
+    vocabulary.scanner().decode_lines(lines)
+}
+
+/// Finds the digits of a [`Vocabulary`] in one pass per direction
+///
+/// Both directions are Aho-Corasick automata: one over the words, run from the
+/// start of a line, and one over the reversed words, run from its end. Each
+/// stops at most one word length after its first match, so lines are never
+/// scanned twice and long lines cost no more than their digits are apart.
+pub struct Scanner {
+    forward: Automaton,
+    backward: Automaton,
+}
+
+impl Scanner {
+    pub fn new(vocabulary: &Vocabulary) -> Self {
+        let digits = (0..10).map(|digit| (vec![b'0' + digit as u8], digit));
+        let words = vocabulary
+            .words
+            .iter()
+            .map(|(word, digit)| (word.as_bytes().to_vec(), *digit));
+        let patterns = digits.chain(words).collect::<Vec<_>>();
+        let reversed = patterns.iter().map(|(word, digit)| {
+            let mut word = word.clone();
+            word.reverse();
+            (word, *digit)
+        });
+        Self {
+            forward: Automaton::new(patterns.iter().cloned()),
+            backward: Automaton::new(reversed),
+        }
+    }
+
+    /// Same as [`Vocabulary::first`]
+    pub fn first(&self, line: &[u8]) -> Option<usize> {
+        self.forward.leftmost(line.iter().copied())
+    }
+
+    /// Same as [`Vocabulary::last`]
+    pub fn last(&self, line: &[u8]) -> Option<usize> {
+        self.backward.leftmost(line.iter().rev().copied())
+    }
+
+    pub fn scan(&self, line: &[u8]) -> Option<Calibration> {
+        let (first, last) = self.first(line).zip(self.last(line))?;
+        Some(Calibration { first, last })
+    }
 
-    let last = ".*(one|two|three|four|five|six|seven|eight|nine|[0-9])";
+    /// The calibrations of the non-empty lines of `reader`, read one at a time
+    pub fn calibrations<R: BufRead>(&self, reader: R) -> Calibrations<'_, R> {
+        Calibrations {
+            scanner: self,
+            reader,
+            buf: vec![],
+            line_no: 0,
+        }
+    }
+
+    pub fn decode(&self, reader: impl BufRead) -> Result<Decoded> {
+        Self::decoded(self.calibrations(reader))
+    }
+
+    pub fn decode_lines(&self, lines: &[String]) -> Result<Decoded> {
+        let calibrations = lines.iter().enumerate().filter_map(|(i, line)| {
+            let line = line.trim();
+            (!line.is_empty()).then(|| self.calibrate(line.as_bytes(), i + 1))
+        });
+        Self::decoded(calibrations)
+    }
+
+    fn decoded(calibrations: impl Iterator<Item = Result<Calibration>>) -> Result<Decoded> {
+        let lines = calibrations.collect::<Result<Vec<_>>>()?;
+        let total = lines.iter().map(Calibration::value).sum();
+        Ok(Decoded { lines, total })
+    }
+
+    /// The calibration of the non-empty `line`, or an error for line `line_no`
+    fn calibrate(&self, line: &[u8], line_no: usize) -> Result<Calibration> {
+        let Some(calibration) = self.scan(line) else {
+            let line = String::from_utf8_lossy(line);
+            let e = ParseError::at(DAY, &line, &line, "a line with a digit");
+            return Err(e.on_line(line_no).into());
+        };
+        trace!("line {line_no}: {calibration:?}");
+        Ok(calibration)
+    }
+}
+
+/// Iterator returned by [`Scanner::calibrations`]
+pub struct Calibrations<'a, R> {
+    scanner: &'a Scanner,
+    reader: R,
+    buf: Vec<u8>,
+    line_no: usize,
+}
+
+impl<R: BufRead> Iterator for Calibrations<'_, R> {
+    type Item = Result<Calibration>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_until(b'\n', &mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => self.line_no += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+            let line = self.buf.trim_ascii();
+            if line.is_empty() {
+                continue;
+            }
+            return Some(self.scanner.calibrate(line, self.line_no));
+        }
+    }
+}
+
+/// Aho-Corasick automaton with the failure links folded into the transitions
+struct Automaton {
+    next: Vec<[u32; 256]>,
+    /// Length and digit of the patterns that end in each state
+    matches: Vec<Vec<(usize, usize)>>,
+    max_len: usize,
+}
+
+impl Automaton {
+    fn new(patterns: impl Iterator<Item = (Vec<u8>, usize)>) -> Self {
+        let mut next = vec![[0u32; 256]];
+        let mut matches = vec![vec![]];
+        let mut max_len = 0;
+        for (pattern, digit) in patterns {
+            let mut state = 0;
+            for &b in &pattern {
+                if next[state][b as usize] == 0 {
+                    next[state][b as usize] = next.len() as u32;
+                    next.push([0; 256]);
+                    matches.push(vec![]);
+                }
+                state = next[state][b as usize] as usize;
+            }
+            matches[state].push((pattern.len(), digit));
+            max_len = max_len.max(pattern.len());
+        }
+
+        // Breadth first, so the failure state of a state is complete before it
+        let mut fail = vec![0; next.len()];
+        let mut queue = next[0]
+            .iter()
+            .filter(|&&s| s != 0)
+            .map(|&s| s as usize)
+            .collect::<VecDeque<_>>();
+        while let Some(state) = queue.pop_front() {
+            let inherited = matches[fail[state]].clone();
+            matches[state].extend(inherited);
+            let fallback = next[fail[state]];
+            for (b, &fallback) in fallback.iter().enumerate() {
+                let child = next[state][b] as usize;
+                if child == 0 {
+                    next[state][b] = fallback;
+                } else {
+                    fail[child] = fallback as usize;
+                    queue.push_back(child);
+                }
+            }
+        }
+        Self {
+            next,
+            matches,
+            max_len,
+        }
+    }
+
+    /// The digit of the longest of the matches that start first
+    fn leftmost(&self, bytes: impl Iterator<Item = u8>) -> Option<usize> {
+        let mut state = 0;
+        let mut best: Option<(usize, usize, usize)> = None;
+        for (i, b) in bytes.enumerate() {
+            if best.is_some_and(|(start, _, _)| i >= start + self.max_len) {
+                break;
+            }
+            state = self.next[state][b as usize] as usize;
+            for &(len, digit) in &self.matches[state] {
+                let start = i + 1 - len;
+                if best.is_none_or(|(s, l, _)| start < s || (start == s && len > l)) {
+                    best = Some((start, len, digit));
+                }
+            }
+        }
+        best.map(|(_, _, digit)| digit)
+    }
+}
+
+pub fn main(path: &Path) -> Result<usize> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(decode(reader, &Vocabulary::english())?.total)
+}
+
+/// Part 2 with regular expressions; a trailing `.*` makes the second one find the last digit
+pub fn calibrate(reader: impl BufRead, with_words: bool) -> Result<usize> {
+    let lines = reader.lines().collect::<io::Result<Vec<_>>>()?;
+    calibrate_lines(&lines, with_words)
+}
+
+/// Same as [`calibrate`], on lines that are already read
+pub fn calibrate_lines(lines: &[String], with_words: bool) -> Result<usize> {
+    let last = if with_words {
+        ".*(one|two|three|four|five|six|seven|eight|nine|[0-9])"
+    } else {
+        ".*([0-9])"
+    };
     let basic = &last[2..];
     let regex_two = Regex::new(last).unwrap();
     let regex_one = Regex::new(basic).unwrap();
 
     let mut acc = 0;
-    let mut buf = String::new();
-    while let Ok(bytes) = reader.read_line(&mut buf) {
-        if bytes == 0 {
-            break;
+    for (i, line) in lines.iter().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
         }
 
-        let line = buf.trim();
-        let first_str = regex_one.find(line).unwrap().as_str();
+        let Some(first_match) = regex_one.find(line) else {
+            let e = ParseError::at(DAY, line, line, "a line with a digit");
+            return Err(e.on_line(line_no).into());
+        };
+        let first_str = first_match.as_str();
         let last_str = regex_two.captures(line).unwrap().get(1).unwrap().as_str();
 
         let first = parse(first_str).unwrap();
@@ -30,11 +386,9 @@ pub fn main(path: &Path) -> io::Result<usize> {
 
         let num = first * 10 + last;
 
-        println!("{line:?} {first_str} {last_str} {first} {last} {num}");
+        trace!("{line:?} {first_str} {last_str} {first} {last} {num}");
 
         acc += num;
-
-        buf.clear();
     }
 
     Ok(acc)
@@ -56,7 +410,7 @@ fn parse(k: &str) -> Option<usize> {
     }
 }
 
-fn _digit_iter(buf: &str, zero: usize) -> impl Iterator<Item = usize> + DoubleEndedIterator + '_ {
+fn _digit_iter(buf: &str, zero: usize) -> impl DoubleEndedIterator<Item = usize> + '_ {
     buf.chars()
         .filter(|c| c.is_ascii_digit())
         .map(move |x| x as usize - zero)
@@ -66,9 +420,167 @@ fn _digit_iter(buf: &str, zero: usize) -> impl Iterator<Item = usize> + DoubleEn
 mod tests {
     use std::path::Path;
 
+    use super::{decode, Day1, Vocabulary, DAY};
+    use crate::answers::expected;
+    use crate::bench::{self, Config};
+    use crate::solution::Solution;
+
+    const EXAMPLE: &str = include_str!("../res/day1/example.txt");
+    const EXAMPLE2: &str = include_str!("../res/day1/example2.txt");
+
+    #[test]
+    fn example1() {
+        assert_eq!(super::calibrate(EXAMPLE.as_bytes(), false).unwrap(), 142);
+    }
+
+    #[test]
+    fn example2() {
+        assert_eq!(super::calibrate(EXAMPLE2.as_bytes(), true).unwrap(), 281);
+    }
+
+    #[test]
+    fn decode_lines() {
+        let decoded = decode(EXAMPLE2.as_bytes(), &Vocabulary::english()).unwrap();
+        let values = decoded.lines.iter().map(|c| c.value()).collect::<Vec<_>>();
+        assert_eq!(values, [29, 83, 13, 24, 42, 14, 76]);
+        assert_eq!(decoded.total, 281);
+        assert_eq!(
+            decode(EXAMPLE.as_bytes(), &Vocabulary::digits())
+                .unwrap()
+                .total,
+            142
+        );
+        let lines = Day1::parse(EXAMPLE2).unwrap();
+        assert_eq!(
+            super::decode_lines(&lines, &Vocabulary::english()).unwrap(),
+            decoded
+        );
+        assert_eq!(super::calibrate_lines(&lines, true).unwrap(), 281);
+    }
+
+    #[test]
+    fn overlaps() {
+        let english = Vocabulary::english();
+        assert_eq!(english.first("eightwo"), Some(8));
+        assert_eq!(english.last("eightwo"), Some(2));
+        assert_eq!(english.first("twone"), Some(2));
+        assert_eq!(english.last("twone"), Some(1));
+        assert_eq!(english.last("oneight"), Some(8));
+        assert_eq!(Vocabulary::digits().first("eightwo"), None);
+
+        let roman = Vocabulary::roman();
+        assert_eq!(roman.first("xVIIIx"), Some(8));
+        assert_eq!(roman.last("xIVx"), Some(4));
+        assert_eq!(roman.last("IIX"), Some(9));
+
+        assert_eq!(Vocabulary::german().last("achtfünfx"), Some(5));
+        assert_eq!(Vocabulary::french().first("xhuitrois"), Some(8));
+        assert_eq!(Vocabulary::french().last("xhuitrois"), Some(3));
+        let custom = Vocabulary::new([("zero", 0), ("ten", 1)]);
+        assert_eq!(custom.first("xzeroten"), Some(0));
+        assert_eq!(custom.last("xzeroten"), Some(1));
+    }
+
+    /// Lines of letters from the digit words, with an occasional digit
+    fn generate(len: usize, seed: u64) -> String {
+        const LETTERS: &[u8] = b"efghinorstuvwx";
+        let mut state = seed;
+        let mut random = move |n: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % n
+        };
+        let mut text = String::with_capacity(len + 100);
+        while text.len() < len {
+            let line_len = 20 + random(60);
+            text.push(char::from(b'1' + random(9) as u8));
+            for _ in 0..line_len {
+                text.push(if random(40) == 0 {
+                    char::from(b'0' + random(10) as u8)
+                } else {
+                    char::from(LETTERS[random(LETTERS.len())])
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    #[test]
+    fn scanner() {
+        for vocabulary in [
+            Vocabulary::digits(),
+            Vocabulary::english(),
+            Vocabulary::roman(),
+            Vocabulary::german(),
+            Vocabulary::new([("ab", 1), ("b", 2), ("abc", 3), ("bcd", 4)]),
+        ] {
+            let scanner = vocabulary.scanner();
+            for line in generate(20_000, 7).lines().chain(["VIIIX", "abcd", "xbcd"]) {
+                let bytes = line.as_bytes();
+                assert_eq!(scanner.first(bytes), vocabulary.first(line), "{line}");
+                assert_eq!(scanner.last(bytes), vocabulary.last(line), "{line}");
+            }
+        }
+
+        let scanner = Vocabulary::english().scanner();
+        let mut lines = scanner.calibrations(EXAMPLE2.as_bytes());
+        assert_eq!(lines.next().unwrap().unwrap().value(), 29);
+        assert_eq!(lines.count(), 6);
+        let e = scanner.decode("1\n\nabc\n".as_bytes()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 1, line 3, column 1: expected a line with a digit, found \"abc\""
+        );
+    }
+
+    /// `cargo test -- --ignored --nocapture day1::tests::scanner_vs_regex`
+    #[test]
+    #[ignore = "benchmark"]
+    fn scanner_vs_regex() {
+        let text = generate(8 << 20, 1);
+        let config = Config { warmup: 1, runs: 5 };
+        let scanner = bench::time(&config, || {
+            decode(text.as_bytes(), &Vocabulary::english()).unwrap()
+        });
+        let regex = bench::time(&config, || super::calibrate(text.as_bytes(), true).unwrap());
+        let median = |mut times: Vec<_>| {
+            times.sort();
+            times[times.len() / 2]
+        };
+        let (scanner, regex) = (median(scanner), median(regex));
+        println!(
+            "{} MiB: scanner {scanner:?}, regex {regex:?}",
+            text.len() >> 20
+        );
+        assert!(scanner < regex);
+    }
+
+    #[test]
+    fn no_digit() {
+        let e = super::calibrate("1abc2\nabc\n".as_bytes(), false).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 1, line 2, column 1: expected a line with a digit, found \"abc\""
+        );
+        let e = decode("1abc2\nabc\n".as_bytes(), &Vocabulary::english()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 1, line 2, column 1: expected a line with a digit, found \"abc\""
+        );
+        let lines = Day1::parse("1abc2\n\n abc\n").unwrap();
+        let e = super::decode_lines(&lines, &Vocabulary::english()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 1, line 3, column 1: expected a line with a digit, found \"abc\""
+        );
+    }
+
     #[test]
+    #[cfg_attr(not(input = "day1"), ignore = "res/day1/input.txt is missing")]
     fn part2() {
         let result = super::main(Path::new("res/day1/input.txt")).unwrap();
-        assert_eq!(result, 54925); // Wrong: 54953, Correct Answer: 54925
+        assert_eq!(result.to_string(), expected(DAY, 2));
     }
 }
@@ -1,10 +1,39 @@
 use std::{collections::BTreeMap, path::Path};
 
-use crate::util::{
-    area::{area, Edge, EdgeMap, Noop},
-    MapDimensions,
+use log::{debug, trace};
+
+use crate::{
+    error::{Error, ParseError, Result},
+    solution::Solution,
+    util::{
+        area::{area, AreaListener, Edge, EdgeMap},
+        grid::Grid,
+        rows, MapDimensions, Pos,
+    },
 };
 
+const DAY: usize = 10;
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    type Input = Map;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        Map::of(text.to_owned())
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        loop_length(input)
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        enclosed_tiles(input)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum Dir {
     Up,
@@ -14,12 +43,12 @@ pub enum Dir {
 }
 
 impl Dir {
-    fn go(&self, (x, y): (usize, usize)) -> (usize, usize) {
+    fn go(&self, (x, y): (usize, usize), dim: &MapDimensions) -> Option<(usize, usize)> {
         match self {
-            Dir::Up => (x, y - 1),
-            Dir::Down => (x, y + 1),
-            Dir::Left => (x - 1, y),
-            Dir::Right => (x + 1, y),
+            Dir::Up => Some((x, y.checked_sub(1)?)),
+            Dir::Down => (y + 1 < dim.height()).then_some((x, y + 1)),
+            Dir::Left => Some((x.checked_sub(1)?, y)),
+            Dir::Right => (x + 1 < dim.width()).then_some((x + 1, y)),
         }
     }
 }
@@ -31,16 +60,25 @@ pub struct Map {
 }
 
 impl Map {
-    pub fn new(path: &Path) -> Self {
-        let text = std::fs::read_to_string(path).unwrap();
-        let dim = MapDimensions::of(&text);
-        let bytes = text.into_bytes();
-        let start = bytes.iter().position(|x| *x == b'S').expect("start");
-        Self {
+    pub fn new(path: &Path) -> Result<Self> {
+        Self::of(std::fs::read_to_string(path)?)
+    }
+
+    pub fn of(text: String) -> Result<Self> {
+        let dim = MapDimensions::check_tiles(DAY, &text, "|-LJ7F.S", "a pipe or ground")?;
+        // every row ends in '\n', so that `dim` indexes the bytes
+        let bytes = rows(&text)
+            .flat_map(|row| row.bytes().chain([b'\n']))
+            .collect::<Vec<_>>();
+        let start = bytes
+            .iter()
+            .position(|&b| b == b'S')
+            .ok_or_else(|| ParseError::at(DAY, &text, &text[text.len()..], "a start tile"))?;
+        Ok(Self {
             bytes,
             dim,
             start: dim.of_index(start),
-        }
+        })
     }
 
     pub fn turn(&self, dir: Dir, (x, y): (usize, usize)) -> Option<(Dir, Edge)> {
@@ -66,53 +104,207 @@ impl Map {
             _ => None,
         }
     }
+
+    /// The pipe at `pos` as drawn in the input, `None` for ground and the start
+    pub fn pipe(&self, pos: Pos<usize>) -> Option<Edge> {
+        Edge::of_pipe(self.bytes[self.dim.index(pos)] as char)
+    }
+}
+
+/// What a tile of the map is once the loop is known
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tile {
+    Loop(Edge),
+    Inside,
+    Outside,
+}
+
+/// Whether a rendering shows the pipes that are not part of the loop
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Junk {
+    Keep,
+    Drop,
+}
+
+/// The loop through the start tile and the tiles it encloses
+pub struct Analysis<'a> {
+    map: &'a Map,
+    path: Vec<Pos<usize>>,
+    border: EdgeMap<usize>,
+    tiles: Grid<Tile>,
+}
+
+impl<'a> Analysis<'a> {
+    pub fn of(map: &'a Map) -> Result<Self> {
+        let (path, border) = scan(map)?;
+        let mut tiles = Grid::from_fn(map.dim.width(), map.dim.height(), |pos| {
+            border
+                .get(&pos)
+                .map_or(Tile::Outside, |&edge| Tile::Loop(edge))
+        });
+        area(
+            0..map.dim.width(),
+            0..map.dim.height(),
+            &border,
+            &mut Classifier(&mut tiles),
+        );
+        Ok(Self {
+            map,
+            path,
+            border,
+            tiles,
+        })
+    }
+
+    /// The tiles of the loop in walking order, starting at the start tile
+    pub fn path(&self) -> &[Pos<usize>] {
+        &self.path
+    }
+
+    /// The pipe hidden under the start tile
+    pub fn start(&self) -> Edge {
+        self.border[&self.map.start]
+    }
+
+    /// The start tile as the puzzle would have drawn it, e.g. `F`
+    pub fn start_pipe(&self) -> char {
+        self.start().to_pipe()
+    }
+
+    pub fn tile(&self, pos: Pos<usize>) -> Tile {
+        self.tiles[pos]
+    }
+
+    /// The tiles enclosed by the loop, row by row
+    pub fn inside(&self) -> impl Iterator<Item = Pos<usize>> + '_ {
+        self.tiles_of(Tile::Inside)
+    }
+
+    /// The tiles neither on nor inside the loop, row by row
+    pub fn outside(&self) -> impl Iterator<Item = Pos<usize>> + '_ {
+        self.tiles_of(Tile::Outside)
+    }
+
+    fn tiles_of(&self, kind: Tile) -> impl Iterator<Item = Pos<usize>> + '_ {
+        self.tiles
+            .iter()
+            .filter(move |(_, &tile)| tile == kind)
+            .map(|(pos, _)| pos)
+    }
+
+    /// Passes the loop and the tiles around it to `listener`, e.g. a
+    /// [`Drawing`](crate::util::area::Drawing)
+    pub fn draw(&self, listener: &mut impl AreaListener<usize>) -> usize {
+        area(
+            0..self.map.dim.width(),
+            0..self.map.dim.height(),
+            &self.border,
+            listener,
+        )
+    }
+
+    /// The loop in double box-drawing lines, the enclosed tiles as `i`
+    ///
+    /// Junk pipes that are kept are drawn in single lines, whether they are
+    /// enclosed or not.
+    pub fn render(&self, junk: Junk) -> String {
+        let mut text = String::new();
+        for y in 0..self.tiles.height() {
+            for x in 0..self.tiles.width() {
+                let pipe = self.map.pipe((x, y)).filter(|_| junk == Junk::Keep);
+                text.push(match (self.tiles[(x, y)], pipe) {
+                    (Tile::Loop(edge), _) => edge.to_box_drawing_char(),
+                    (_, Some(edge)) => thin_box_drawing_char(edge),
+                    (Tile::Inside, None) => 'i',
+                    (Tile::Outside, None) => ' ',
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Marks the enclosed tiles of a grid in which the loop is already marked
+struct Classifier<'g>(&'g mut Grid<Tile>);
+
+impl AreaListener<usize> for Classifier<'_> {
+    fn on_inside(&mut self, xy: Pos<usize>) {
+        self.0[xy] = Tile::Inside;
+    }
 }
 
-pub fn run(path: &Path) -> (usize, usize) {
-    let map = Map::new(path);
-    let border = scan(&map).unwrap();
-    let len = border.len() / 2;
-    let in_fields = area(0..map.dim.width(), 0..map.dim.height(), &border, &mut Noop);
-    (len, in_fields)
+fn thin_box_drawing_char(edge: Edge) -> char {
+    match edge {
+        Edge::SouthEast => '┌',
+        Edge::NorthEast => '└',
+        Edge::SouthWest => '┐',
+        Edge::NorthWest => '┘',
+        Edge::NorthSouth => '│',
+        Edge::EastWest => '─',
+    }
+}
+
+pub fn run(path: &Path) -> Result<(usize, usize)> {
+    let map = Map::new(path)?;
+    let analysis = Analysis::of(&map)?;
+    Ok((analysis.path().len() / 2, analysis.inside().count()))
+}
+
+pub fn loop_length(map: &Map) -> Result<usize> {
+    Ok(scan(map)?.0.len() / 2)
 }
 
-fn scan(map: &Map) -> Option<EdgeMap<usize>> {
+pub fn enclosed_tiles(map: &Map) -> Result<usize> {
+    let analysis = Analysis::of(map)?;
+    debug!("the start tile is {}", analysis.start_pipe());
+    Ok(analysis.inside().count())
+}
+
+/// The tiles of the loop in walking order, and their edges
+fn scan(map: &Map) -> Result<(Vec<Pos<usize>>, EdgeMap<usize>)> {
     let (sx, sy) = map.start;
     let mut border = BTreeMap::new();
-    eprintln!("{sx},{sy}");
+    let mut path = vec![map.start];
+    debug!("start at {sx},{sy}");
     use Dir::*;
     for start_dir in [Up, Down, Left, Right] {
         let mut dir = start_dir;
-        eprintln!("{dir:?}");
+        trace!("{dir:?}");
+        path.truncate(1);
+        border.clear();
         let (mut x, mut y) = (sx, sy);
-        loop {
-            (x, y) = dir.go((x, y));
-            eprintln!("{dir:?} to {x},{y}");
+        while let Some(next) = dir.go((x, y), &map.dim) {
+            (x, y) = next;
+            trace!("{dir:?} to {x},{y}");
             if (x, y) == (sx, sy) {
                 let start_edge = start_edge(start_dir, dir);
                 border.insert((sx, sy), start_edge);
-                return Some(border);
+                return Ok((path, border));
             }
             if let Some((next_dir, edge)) = map.turn(dir, (x, y)) {
                 border.insert((x, y), edge);
+                path.push((x, y));
                 dir = next_dir;
             } else {
                 break;
             }
         }
     }
-    None
+    Err(Error::unsolvable(DAY, "no loop through the start tile"))
 }
 
+/// The pipe under the start tile, left towards `start_dir` and entered
+/// again going `dir`
 fn start_edge(start_dir: Dir, dir: Dir) -> Edge {
     use Dir::*;
     match (start_dir, dir) {
         (Up, Up) | (Down, Down) => Edge::NorthSouth,
         (Up, Down) | (Down, Up) => unreachable!(),
-        (Up, Left) | (Left, Up) => Edge::NorthEast,
-        (Up, Right) | (Right, Up) => Edge::NorthWest,
-        (Down, Left) | (Left, Down) => Edge::SouthEast,
-        (Down, Right) | (Right, Down) => Edge::SouthWest,
+        (Up, Left) | (Right, Down) => Edge::NorthEast,
+        (Up, Right) | (Left, Down) => Edge::NorthWest,
+        (Down, Left) | (Right, Up) => Edge::SouthEast,
+        (Down, Right) | (Left, Up) => Edge::SouthWest,
         (Left, Left) | (Right, Right) => Edge::EastWest,
         (Left, Right) | (Right, Left) => unreachable!(),
     }
@@ -122,19 +314,129 @@ fn start_edge(start_dir: Dir, dir: Dir) -> Edge {
 mod tests {
     use std::path::Path;
 
-    use super::run;
+    use crate::util::area::{Drawing, Edge};
+
+    use super::{
+        enclosed_tiles, loop_length, run, start_edge, Analysis, Dir, Junk, Map, Tile, DAY,
+    };
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day10/example.txt");
 
     #[test]
     fn example() {
-        let (len, in_fields) = run(Path::new("res/day10/example.txt"));
-        assert_eq!(len, 8);
-        assert_eq!(in_fields, 1);
+        let map = Map::of(EXAMPLE.to_owned()).unwrap();
+        assert_eq!(loop_length(&map).unwrap(), 8);
+        assert_eq!(enclosed_tiles(&map).unwrap(), 1);
     }
 
     #[test]
+    fn line_endings() {
+        for text in [EXAMPLE.trim_end().to_owned(), EXAMPLE.replace('\n', "\r\n")] {
+            let map = Map::of(text).unwrap();
+            assert_eq!(loop_length(&map).unwrap(), 8);
+            assert_eq!(enclosed_tiles(&map).unwrap(), 1);
+        }
+    }
+
+    #[test]
+    fn no_loop() {
+        let map = Map::of("S-7\n..|\n".to_owned()).unwrap();
+        assert_eq!(
+            loop_length(&map).unwrap_err().to_string(),
+            "day 10: no loop through the start tile"
+        );
+        let e = Map::of("...\n.x.\n".to_owned()).err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "day 10, line 2, column 2: expected a pipe or ground, found \"x\""
+        );
+    }
+
+    #[test]
+    #[cfg_attr(not(input = "day10"), ignore = "res/day10/input.txt is missing")]
     fn input() {
-        let (len, in_fields) = run(Path::new("res/day10/input.txt"));
-        assert_eq!(len, 6951);
-        assert_eq!(in_fields, 563);
+        let (len, in_fields) = run(Path::new("res/day10/input.txt")).unwrap();
+        assert_eq!(len.to_string(), expected(DAY, 1));
+        assert_eq!(in_fields.to_string(), expected(DAY, 2));
+    }
+
+    #[test]
+    fn analysis() {
+        let map = Map::of(EXAMPLE.to_owned()).unwrap();
+        let analysis = Analysis::of(&map).unwrap();
+        assert_eq!(analysis.start(), Edge::SouthEast);
+        assert_eq!(analysis.start_pipe(), 'F');
+        assert_eq!(analysis.path().len(), 16);
+        assert_eq!(analysis.path()[..3], [(0, 2), (0, 3), (0, 4)]);
+        assert_eq!(analysis.tile((2, 2)), Tile::Inside);
+        assert_eq!(analysis.tile((0, 2)), Tile::Loop(Edge::SouthEast));
+        assert_eq!(analysis.inside().collect::<Vec<_>>(), [(2, 2)]);
+        assert_eq!(analysis.outside().count(), 25 - 16 - 1);
+
+        let loop_only = "  ╔╗ \n ╔╝║ \n╔╝i╚╗\n║╔══╝\n╚╝   \n";
+        assert_eq!(analysis.render(Junk::Drop), loop_only);
+        let mut drawing = Drawing::in_memory();
+        assert_eq!(analysis.draw(&mut drawing), 1);
+        assert_eq!(drawing.content(), loop_only);
+    }
+
+    #[test]
+    fn junk() {
+        let map = Map::of("-L|F7\n7S-7|\nL|7||\n-L-J|\nL|-JF\n".to_owned()).unwrap();
+        let analysis = Analysis::of(&map).unwrap();
+        assert_eq!(analysis.start(), Edge::SouthEast);
+        assert_eq!(analysis.path().len(), 8);
+        assert_eq!(analysis.inside().collect::<Vec<_>>(), [(2, 2)]);
+        assert_eq!(
+            analysis.render(Junk::Drop),
+            "     \n ╔═╗ \n ║i║ \n ╚═╝ \n     \n"
+        );
+        assert_eq!(
+            analysis.render(Junk::Keep),
+            "─└│┌┐\n┐╔═╗│\n└║┐║│\n─╚═╝│\n└│─┘┌\n"
+        );
+    }
+
+    #[test]
+    fn start_shapes() {
+        use Dir::*;
+        let dirs = [Up, Down, Left, Right];
+        // the start tile connects `start_dir` with where `dir` came from
+        let opposite = |d| match d {
+            Up => Down,
+            Down => Up,
+            Left => Right,
+            Right => Left,
+        };
+        let name = |d| match d {
+            Up => 'N',
+            Down => 'S',
+            Left => 'W',
+            Right => 'E',
+        };
+        for start_dir in dirs {
+            for dir in dirs
+                .into_iter()
+                .filter(|&d| name(d) != name(opposite(start_dir)))
+            {
+                let mut ends = [name(start_dir), name(opposite(dir))];
+                ends.sort_unstable();
+                let expected = match ends {
+                    ['N', 'S'] => Edge::NorthSouth,
+                    ['E', 'W'] => Edge::EastWest,
+                    ['E', 'N'] => Edge::NorthEast,
+                    ['N', 'W'] => Edge::NorthWest,
+                    ['E', 'S'] => Edge::SouthEast,
+                    ['S', 'W'] => Edge::SouthWest,
+                    _ => unreachable!(),
+                };
+                assert_eq!(
+                    start_edge(start_dir, dir),
+                    expected,
+                    "{start_dir:?} {dir:?}"
+                );
+            }
+        }
     }
 }
@@ -1,26 +1,61 @@
 use std::path::Path;
 
-pub fn run(path: &Path, multiplier: usize) -> usize {
-    let text = std::fs::read_to_string(path).unwrap();
-    let _width = text.chars().position(|x| x == '\n').unwrap();
-    let _stride = _width + 1;
-    let _height = text.len() / _stride;
-    let _bytes = text.as_bytes();
-
-    let blank_rows = text
-        .lines()
+use log::trace;
+
+use crate::{error::Result, solution::Solution, util::Grid};
+
+const DAY: usize = 11;
+
+pub struct Day11;
+
+impl Solution for Day11 {
+    type Input = Grid<bool>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        Ok(distance_sum(input, 2))
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        Ok(distance_sum(input, 1000000))
+    }
+}
+
+pub fn run(path: &Path, multiplier: usize) -> Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(distance_sum(&parse(&text)?, multiplier))
+}
+
+/// Parses the image, with `true` for galaxies
+pub fn parse(text: &str) -> Result<Grid<bool>> {
+    Ok(Grid::parse(DAY, text, "'.' or '#'", |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })?)
+}
+
+pub fn distance_sum(image: &Grid<bool>, multiplier: usize) -> usize {
+    let blank_rows = image
+        .rows()
         .enumerate()
-        .filter_map(|(i, text)| text.chars().all(|c| c == '.').then_some(i))
+        .filter_map(|(i, row)| row.iter().all(|g| !g).then_some(i))
         .collect::<Vec<_>>();
 
-    let blank_cols = (0.._width)
-        .filter(|x| (0.._height).all(|y| text.as_bytes()[y * _stride + x] == b'.'))
+    let blank_cols = image
+        .cols()
+        .enumerate()
+        .filter_map(|(i, mut col)| col.all(|g| !g).then_some(i))
         .collect::<Vec<_>>();
 
-    let galaxies = text
-        .bytes()
-        .enumerate()
-        .filter_map(|(i, b)| (b == b'#').then_some((i % _stride, i / _stride)))
+    let galaxies = image
+        .iter()
+        .filter_map(|(pos, g)| g.then_some(pos))
         .collect::<Vec<_>>();
 
     let mut sum = 0;
@@ -36,13 +71,12 @@ pub fn run(path: &Path, multiplier: usize) -> usize {
 
             let xspaces = col1.abs_diff(col2);
             let yspaces = row1.abs_diff(row2);
-            eprintln!("x{xspaces} y{yspaces}");
 
             let xdiff = g1.0.abs_diff(g2.0) + xspaces * (multiplier - 1);
             let ydiff = g1.1.abs_diff(g2.1) + yspaces * (multiplier - 1);
 
             let diff = xdiff + ydiff;
-            eprintln!("{diff}");
+            trace!("{g1:?} {g2:?}: x{xspaces} y{yspaces} {diff}");
             sum += diff;
         }
     }
@@ -54,28 +88,43 @@ pub fn run(path: &Path, multiplier: usize) -> usize {
 mod tests {
     use std::path::Path;
 
-    use super::run;
+    use super::{distance_sum, parse, run, DAY};
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day11/example.txt");
 
     #[test]
     fn example1() {
-        assert_eq!(run(Path::new("res/day11/example.txt"), 2), 374);
+        assert_eq!(distance_sum(&parse(EXAMPLE).unwrap(), 2), 374);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day11"), ignore = "res/day11/input.txt is missing")]
     fn part1() {
-        assert_eq!(run(Path::new("res/day11/input.txt"), 2), 9177603);
+        let len = run(Path::new("res/day11/input.txt"), 2).unwrap();
+        assert_eq!(len.to_string(), expected(DAY, 1));
     }
 
     #[test]
     fn example2() {
-        assert_eq!(run(Path::new("res/day11/example.txt"), 10), 1030);
-        assert_eq!(run(Path::new("res/day11/example.txt"), 100), 8410);
+        let image = parse(EXAMPLE).unwrap();
+        assert_eq!(distance_sum(&image, 10), 1030);
+        assert_eq!(distance_sum(&image, 100), 8410);
+    }
+
+    #[test]
+    fn invalid_tile() {
+        let e = parse("#.\n.o\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 11, line 2, column 2: expected '.' or '#', found \"o\""
+        );
     }
 
     #[test]
+    #[cfg_attr(not(input = "day11"), ignore = "res/day11/input.txt is missing")]
     fn part2() {
-        let len = run(Path::new("res/day11/input.txt"), 1000000);
-        assert_ne!(len, 632004545607);
-        assert_eq!(len, 632003913611);
+        let len = run(Path::new("res/day11/input.txt"), 1000000).unwrap();
+        assert_eq!(len.to_string(), expected(DAY, 2));
     }
 }
@@ -1,16 +1,75 @@
-pub fn parse(s: &str) -> (&str, Vec<usize>) {
-    let (pattern, rest) = s.split_once(' ').unwrap();
+use crate::{
+    error::{ParseError, Result},
+    solution::Solution,
+};
+
+const DAY: usize = 12;
+
+pub struct Day12;
+
+impl Solution for Day12 {
+    type Input = Vec<Row>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse_rows(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        Ok(part1::arrangements(input, 1))
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        Ok(part2::arrangements(input, 5))
+    }
+}
+
+/// A row of springs and the lengths of its groups of broken springs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub pattern: String,
+    pub lengths: Vec<usize>,
+}
+
+/// Parses one line of springs, e.g. `???.### 1,1,3`
+pub fn parse(s: &str) -> Result<(&str, Vec<usize>), ParseError> {
+    let (pattern, rest) = s
+        .split_once(' ')
+        .ok_or_else(|| ParseError::at(DAY, s, s, "a pattern and lengths"))?;
+    if let Some(pos) = pattern.find(|c| !matches!(c, '#' | '.' | '?')) {
+        let c = &pattern[pos..pos + pattern[pos..].chars().next().unwrap().len_utf8()];
+        return Err(ParseError::at(DAY, s, c, "'#', '.' or '?'"));
+    }
     let v = rest
         .split(',')
-        .map(str::parse)
-        .map(Result::unwrap)
-        .collect::<Vec<usize>>();
-    (pattern, v)
+        .map(|n| match n.parse() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(ParseError::at(DAY, s, n, "a positive number")),
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+    Ok((pattern, v))
+}
+
+pub fn parse_rows(text: &str) -> Result<Vec<Row>> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| match parse(line) {
+            Ok((pattern, lengths)) => Ok(Row {
+                pattern: pattern.to_owned(),
+                lengths,
+            }),
+            Err(e) => Err(e.on_line(i + 1).into()),
+        })
+        .collect()
 }
 
 pub mod part1 {
     use std::path::Path;
 
+    use super::Row;
+    use crate::error::Result;
+
     pub fn variants<I: Iterator<Item = char> + Clone, L: Iterator<Item = usize> + Clone>(
         mut chars: I,
         lengths: L,
@@ -62,11 +121,14 @@ pub mod part1 {
         }
     }
 
-    pub fn run(path: &Path, mul: usize) -> usize {
-        let text = std::fs::read_to_string(path).unwrap();
-        text.lines()
-            .map(super::parse)
-            .map(|(pattern, lengths)| {
+    pub fn run(path: &Path, mul: usize) -> Result<usize> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(arrangements(&super::parse_rows(&text)?, mul))
+    }
+
+    pub fn arrangements(rows: &[Row], mul: usize) -> usize {
+        rows.iter()
+            .map(|Row { pattern, lengths }| {
                 let chars = pattern.chars().cycle().take(pattern.len() * mul);
                 let lengths = lengths.iter().copied().cycle().take(lengths.len() * mul);
                 variants(chars, lengths)
@@ -78,6 +140,9 @@ pub mod part1 {
 pub mod part2 {
     use std::{collections::BTreeMap, path::Path};
 
+    use super::Row;
+    use crate::error::Result;
+
     #[derive(Debug, PartialEq, Eq, Clone, Copy)]
     pub enum State {
         // Free to start a new broken sequence
@@ -177,11 +242,14 @@ pub mod part2 {
         states
     }
 
-    pub fn run(path: &Path, mul: usize) -> usize {
-        let text = std::fs::read_to_string(path).unwrap();
-        text.lines()
-            .map(super::parse)
-            .map(|(pattern, lengths)| variants(pattern, &lengths, mul))
+    pub fn run(path: &Path, mul: usize) -> Result<usize> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(arrangements(&super::parse_rows(&text)?, mul))
+    }
+
+    pub fn arrangements(rows: &[Row], mul: usize) -> usize {
+        rows.iter()
+            .map(|row| variants(&row.pattern, &row.lengths, mul))
             .sum()
     }
 }
@@ -192,30 +260,46 @@ mod tests {
 
     use super::{
         part1::{run, variants},
-        part2,
+        part2, DAY,
     };
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day12/example.txt");
 
     #[test]
     fn parse() {
-        let (pattern, lengths) = super::parse("?#?#?#?#?#?#?#? 1,3,1,6");
+        let (pattern, lengths) = super::parse("?#?#?#?#?#?#?#? 1,3,1,6").unwrap();
         assert_eq!(pattern, "?#?#?#?#?#?#?#?");
         assert_eq!(lengths, &[1, 3, 1, 6]);
         assert_eq!(variants(pattern.chars(), lengths.iter().copied()), 1);
 
-        let (pattern, lengths) = super::parse("?###???????? 3,2,1");
+        let (pattern, lengths) = super::parse("?###???????? 3,2,1").unwrap();
         assert_eq!(pattern, "?###????????");
         assert_eq!(lengths, &[3, 2, 1]);
         assert_eq!(variants(pattern.chars(), lengths.iter().copied()), 10);
     }
 
+    #[test]
+    fn parse_error() {
+        let e = super::parse_rows("???.### 1,1,3\n.??..?? 1,x\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 12, line 2, column 11: expected a positive number, found \"x\""
+        );
+    }
+
     #[test]
     fn example1() {
-        assert_eq!(run(Path::new("res/day12/example.txt"), 1), 21);
+        let rows = super::parse_rows(EXAMPLE).unwrap();
+        assert_eq!(super::part1::arrangements(&rows, 1), 21);
+        assert_eq!(part2::arrangements(&rows, 5), 525152);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day12"), ignore = "res/day12/input.txt is missing")]
     fn part1() {
-        assert_eq!(run(Path::new("res/day12/input.txt"), 1), 6827);
+        let part1 = run(Path::new("res/day12/input.txt"), 1).unwrap();
+        assert_eq!(part1.to_string(), expected(DAY, 1));
     }
 
     #[test]
@@ -280,10 +364,9 @@ mod tests {
     }
 
     #[test]
+    #[cfg_attr(not(input = "day12"), ignore = "res/day12/input.txt is missing")]
     fn part2() {
-        assert_eq!(
-            part2::run(Path::new("res/day12/input.txt"), 5),
-            1537505634471
-        );
+        let part2 = part2::run(Path::new("res/day12/input.txt"), 5).unwrap();
+        assert_eq!(part2.to_string(), expected(DAY, 2));
     }
 }
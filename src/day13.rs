@@ -1,7 +1,34 @@
 use std::{ops::BitXor, path::Path};
 
-pub fn run(path: &Path) -> usize {
-    let patterns = decode(path);
+use crate::{error::Result, solution::Solution, util::Grid};
+
+const DAY: usize = 13;
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    type Input = Vec<Patterns>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse_patterns(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        Ok(summarize(input))
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        Ok(summarize_part2(input))
+    }
+}
+
+pub fn run(path: &Path) -> Result<usize> {
+    Ok(summarize(&decode(path)?))
+}
+
+pub fn summarize(patterns: &[Patterns]) -> usize {
     let mut sum = 0;
     for pat in patterns {
         for h in find_pairs(&pat.rows) {
@@ -18,8 +45,11 @@ pub fn run(path: &Path) -> usize {
     sum
 }
 
-pub fn run_part2(path: &Path) -> usize {
-    let patterns = decode(path);
+pub fn run_part2(path: &Path) -> Result<usize> {
+    Ok(summarize_part2(&decode(path)?))
+}
+
+pub fn summarize_part2(patterns: &[Patterns]) -> usize {
     let mut sum = 0;
     for pat in patterns {
         for h in 1..pat.rows.len() {
@@ -36,14 +66,31 @@ pub fn run_part2(path: &Path) -> usize {
     sum
 }
 
-fn decode(path: &Path) -> Vec<Patterns> {
-    let _text = std::fs::read_to_string(path).unwrap();
-    let patterns = _text.split("\n\n").collect::<Vec<_>>();
-    patterns
-        .iter()
-        .copied()
-        .map(parse_line_pattern)
-        .collect::<Vec<_>>()
+fn decode(path: &Path) -> Result<Vec<Patterns>> {
+    let _text = std::fs::read_to_string(path)?;
+    parse_patterns(&_text)
+}
+
+pub fn parse_patterns(text: &str) -> Result<Vec<Patterns>> {
+    let mut patterns = vec![];
+    // offset and line number of the first line of the pattern
+    let (mut start, mut first_line) = (0, 1);
+    let mut offset = 0;
+    // an empty line after the last line ends the last pattern
+    for (index, line) in text.split_inclusive('\n').chain([""]).enumerate() {
+        if line.trim_end_matches(['\r', '\n']).is_empty() {
+            let pat = &text[start..offset];
+            if !pat.is_empty() {
+                let grid = Grid::parse(DAY, pat, "'.' or '#'", parse_char)
+                    .map_err(|e| e.on_line(first_line))?;
+                patterns.push(Patterns::of(&grid));
+            }
+            start = offset + line.len();
+            first_line = index + 2;
+        }
+        offset += line.len();
+    }
+    Ok(patterns)
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -52,6 +99,16 @@ pub struct Patterns {
     pub cols: Vec<usize>,
 }
 
+impl Patterns {
+    /// Encodes each row and column as bits, with rocks as `1`
+    pub fn of(grid: &Grid<bool>) -> Self {
+        Self {
+            rows: grid.rows().map(|row| scanlines(row.iter())).collect(),
+            cols: grid.cols().map(scanlines).collect(),
+        }
+    }
+}
+
 pub fn find_pairs(scanlines: &[usize]) -> impl Iterator<Item = usize> + '_ {
     (1..scanlines.len()).filter(|x| scanlines[*x - 1] == scanlines[*x])
 }
@@ -82,39 +139,15 @@ pub fn check_reflection(scanlines: &[usize], index: usize) -> bool {
     (0..space).all(|x| scanlines[index + x] == scanlines[index - 1 - x])
 }
 
-fn parse_line_pattern(pat: &str) -> Patterns {
-    Patterns {
-        rows: pat.lines().map(parse_line).collect::<Vec<_>>(),
-        cols: parse_col_patterns(pat),
-    }
-}
-
-fn parse_col_patterns(pat: &str) -> Vec<usize> {
-    let width = pat.split_once('\n').unwrap().0.len();
-    let stride = width + 1;
-    let height = (pat.len() + 1) / stride;
-    let mut cols = Vec::with_capacity(width);
-    for x in 0..width {
-        let mut acc = 0;
-        for y in 0..height {
-            let ch = pat.split_at(y * stride + x).1.chars().next().unwrap();
-            acc = acc << 1 | parse_char(ch);
-        }
-        cols.push(acc);
-    }
-    cols
-}
-
-fn parse_line(line: &str) -> usize {
-    line.chars()
-        .fold(0, |acc, next| acc << 1 | parse_char(next))
+fn scanlines<'a>(cells: impl Iterator<Item = &'a bool>) -> usize {
+    cells.fold(0, |acc, rock| acc << 1 | usize::from(*rock))
 }
 
-fn parse_char(next: char) -> usize {
+fn parse_char(next: char) -> Option<bool> {
     match next {
-        '.' => 0, // ash
-        '#' => 1, // rocks
-        _ => panic!(),
+        '.' => Some(false), // ash
+        '#' => Some(true),  // rocks
+        _ => None,
     }
 }
 
@@ -124,13 +157,14 @@ mod tests {
 
     use crate::day13::check_reflection;
 
-    use super::{decode, run, run_part2};
+    use super::{parse_patterns, run, run_part2, summarize, summarize_part2, DAY};
+    use crate::answers::expected;
 
-    const EXAMPLE: &str = "res/day13/example.txt";
+    const EXAMPLE: &str = include_str!("../res/day13/example.txt");
 
     #[test]
     fn example1() {
-        let _pats = decode(Path::new(EXAMPLE));
+        let _pats = parse_patterns(EXAMPLE).unwrap();
         assert_eq!(
             _pats[0].rows,
             &[
@@ -157,22 +191,43 @@ mod tests {
         assert!(check_reflection(&_pats[0].cols, 5));
         assert!(!check_reflection(&_pats[0].cols, 6));
 
-        let sum = run(Path::new(EXAMPLE));
-        assert_eq!(sum, 405);
+        assert_eq!(summarize(&_pats), 405);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day13"), ignore = "res/day13/input.txt is missing")]
     fn part1() {
-        assert_eq!(run(Path::new("res/day13/input.txt")), 34821);
+        let sum = run(Path::new("res/day13/input.txt")).unwrap();
+        assert_eq!(sum.to_string(), expected(DAY, 1));
+    }
+
+    #[test]
+    fn ragged() {
+        let e = parse_patterns("#.#\n##.\n\n.#\n#\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 13, line 5, column 1: expected a line of width 2, found \"#\""
+        );
+    }
+
+    #[test]
+    fn line_endings() {
+        let patterns = parse_patterns(EXAMPLE).unwrap();
+        for text in [EXAMPLE.trim_end().to_owned(), EXAMPLE.replace('\n', "\r\n")] {
+            assert_eq!(parse_patterns(&text).unwrap(), patterns);
+        }
     }
 
     #[test]
     fn example2() {
-        assert_eq!(run_part2(Path::new("res/day13/example.txt")), 400);
+        let patterns = parse_patterns(EXAMPLE).unwrap();
+        assert_eq!(summarize_part2(&patterns), 400);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day13"), ignore = "res/day13/input.txt is missing")]
     fn part2() {
-        assert_eq!(run_part2(Path::new("res/day13/input.txt")), 36919);
+        let sum = run_part2(Path::new("res/day13/input.txt")).unwrap();
+        assert_eq!(sum.to_string(), expected(DAY, 2));
     }
 }
@@ -5,26 +5,78 @@ use std::{
     path::Path,
 };
 
-pub fn run_part1(path: &Path) -> usize {
-    let _text = std::fs::read_to_string(path).unwrap();
-    let width = _text.split_once('\n').unwrap().0.len();
-    let stride = width + 1;
-    let height = _text.len() / stride;
-    let mut max = vec![height; width];
+use log::debug;
+
+use crate::{error::Result, solution::Solution, util::Grid};
+
+const DAY: usize = 14;
+
+pub struct Day14;
+
+impl Solution for Day14 {
+    type Input = Grid<Rock>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        Ok(north_load(input))
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        Ok(Part2::of(input.clone()).find_loop())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Rock {
+    /// `O`
+    Round,
+    /// `#`
+    Cube,
+    /// `.`
+    Empty,
+}
+
+impl Rock {
+    fn of_char(c: char) -> Option<Self> {
+        match c {
+            'O' => Some(Self::Round),
+            '#' => Some(Self::Cube),
+            '.' => Some(Self::Empty),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Grid<Rock>> {
+    Ok(Grid::parse(DAY, text, "'.', '#' or 'O'", Rock::of_char)?)
+}
+
+pub fn run_part1(path: &Path) -> Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(north_load(&parse(&text)?))
+}
+
+pub fn north_load(grid: &Grid<Rock>) -> usize {
+    let height = grid.height();
+    let mut max = vec![height; grid.width()];
 
     let mut sum = 0;
-    for (y, line) in _text.lines().enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            match c {
-                '#' => {
+    for (y, line) in grid.rows().enumerate() {
+        for (x, rock) in line.iter().enumerate() {
+            match rock {
+                Rock::Cube => {
                     max[x] = height - y - 1;
                 }
-                '.' => { /* do nothing */ }
-                'O' => {
+                Rock::Empty => { /* do nothing */ }
+                Rock::Round => {
                     let next = max[x] - 1;
                     sum += mem::replace(&mut max[x], next);
                 }
-                _ => panic!("{c}"),
             }
         }
     }
@@ -32,36 +84,35 @@ pub fn run_part1(path: &Path) -> usize {
 }
 
 pub struct Part2 {
-    bytes: Vec<u8>,
+    grid: Grid<Rock>,
     width: usize,
     height: usize,
-    stride: usize,
     bhash: BuildHasherDefault<DefaultHasher>,
 }
 
 impl Part2 {
     pub fn hash(&self) -> u64 {
-        self.bhash.hash_one(&self.bytes)
+        self.bhash.hash_one(&self.grid)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rock {
+        self.grid[(x, y)]
     }
 
-    pub fn get(&self, x: usize, y: usize) -> u8 {
-        self.bytes[y * self.stride + x]
+    pub fn get_mut(&mut self, x: usize, y: usize) -> &mut Rock {
+        &mut self.grid[(x, y)]
     }
 
-    pub fn get_mut(&mut self, x: usize, y: usize) -> &mut u8 {
-        &mut self.bytes[y * self.stride + x]
+    pub fn new(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(Self::of(parse(&text)?))
     }
 
-    pub fn new(path: &Path) -> Self {
-        let text = std::fs::read_to_string(path).unwrap();
-        let width = text.split_once('\n').unwrap().0.len();
-        let stride = width + 1;
-        let height = text.len() / stride;
+    pub fn of(grid: Grid<Rock>) -> Self {
         Self {
-            bytes: text.clone().into_bytes(),
-            width,
-            height,
-            stride,
+            width: grid.width(),
+            height: grid.height(),
+            grid,
             bhash: BuildHasherDefault::<DefaultHasher>::default(),
         }
     }
@@ -89,7 +140,7 @@ impl Part2 {
         let cycle = count - prev_index;
         let rest = 1000000000 - count;
         let rem: usize = rest % cycle;
-        eprintln!("{prev_index} + n x {cycle}; {rest}; {rem}");
+        debug!("{prev_index} + n x {cycle}; {rest}; {rem}");
         for _ in 0..rem {
             self.cycle();
         }
@@ -109,11 +160,10 @@ impl Part2 {
     }
 
     pub fn load(&self) -> usize {
-        self.bytes
-            .chunks(self.stride)
-            .map(|a| &a[..self.width])
+        self.grid
+            .rows()
             .enumerate()
-            .map(|(y, line)| line.iter().filter(|&x| *x == b'O').count() * (self.height - y))
+            .map(|(y, line)| line.iter().filter(|&x| *x == Rock::Round).count() * (self.height - y))
             .sum()
     }
 
@@ -122,18 +172,17 @@ impl Part2 {
         for y in 0..self.height {
             for (x, m) in min.iter_mut().enumerate() {
                 match self.get(x, y) {
-                    b'#' => {
+                    Rock::Cube => {
                         *m = y + 1;
                     }
-                    b'.' => { /* do nothing */ }
-                    b'O' => {
+                    Rock::Empty => { /* do nothing */ }
+                    Rock::Round => {
                         let prev = *m;
                         let next = prev + 1;
-                        *self.get_mut(x, y) = b'.';
-                        *self.get_mut(x, prev) = b'O';
+                        *self.get_mut(x, y) = Rock::Empty;
+                        *self.get_mut(x, prev) = Rock::Round;
                         *m = next;
                     }
-                    c => panic!("{c}"),
                 }
             }
         }
@@ -144,18 +193,17 @@ impl Part2 {
         for x in 0..self.width {
             for (y, m) in min.iter_mut().enumerate() {
                 match self.get(x, y) {
-                    b'#' => {
+                    Rock::Cube => {
                         *m = x + 1;
                     }
-                    b'.' => { /* do nothing */ }
-                    b'O' => {
+                    Rock::Empty => { /* do nothing */ }
+                    Rock::Round => {
                         let prev = *m;
                         let next = prev + 1;
-                        *self.get_mut(x, y) = b'.';
-                        *self.get_mut(prev, y) = b'O';
+                        *self.get_mut(x, y) = Rock::Empty;
+                        *self.get_mut(prev, y) = Rock::Round;
                         *m = next;
                     }
-                    c => panic!("{c}"),
                 }
             }
         }
@@ -166,18 +214,17 @@ impl Part2 {
         for y in (0..self.height).rev() {
             for (x, m) in max.iter_mut().enumerate() {
                 match self.get(x, y) {
-                    b'#' => {
+                    Rock::Cube => {
                         *m = y;
                     }
-                    b'.' => { /* do nothing */ }
-                    b'O' => {
+                    Rock::Empty => { /* do nothing */ }
+                    Rock::Round => {
                         let prev = *m;
                         let next = prev - 1;
-                        *self.get_mut(x, y) = b'.';
-                        *self.get_mut(x, next) = b'O';
+                        *self.get_mut(x, y) = Rock::Empty;
+                        *self.get_mut(x, next) = Rock::Round;
                         *m = next;
                     }
-                    c => panic!("{c}"),
                 }
             }
         }
@@ -188,18 +235,17 @@ impl Part2 {
         for x in (0..self.width).rev() {
             for (y, m) in max.iter_mut().enumerate() {
                 match self.get(x, y) {
-                    b'#' => {
+                    Rock::Cube => {
                         *m = x;
                     }
-                    b'.' => { /* do nothing */ }
-                    b'O' => {
+                    Rock::Empty => { /* do nothing */ }
+                    Rock::Round => {
                         let prev = *m;
                         let next = prev - 1;
-                        *self.get_mut(x, y) = b'.';
-                        *self.get_mut(next, y) = b'O';
+                        *self.get_mut(x, y) = Rock::Empty;
+                        *self.get_mut(next, y) = Rock::Round;
                         *m = next;
                     }
-                    c => panic!("{c}"),
                 }
             }
         }
@@ -210,42 +256,56 @@ impl Part2 {
 mod tests {
     use std::path::Path;
 
-    use super::{run_part1, Part2};
+    use super::{north_load, parse, run_part1, Part2, DAY};
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day14/example.txt");
 
     #[test]
     fn example1() {
-        assert_eq!(run_part1(Path::new("res/day14/example.txt")), 136);
+        assert_eq!(north_load(&parse(EXAMPLE).unwrap()), 136);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day14"), ignore = "res/day14/input.txt is missing")]
     fn part1() {
-        assert_eq!(run_part1(Path::new("res/day14/input.txt")), 105784);
+        let load = run_part1(Path::new("res/day14/input.txt")).unwrap();
+        assert_eq!(load.to_string(), expected(DAY, 1));
+    }
+
+    #[test]
+    fn invalid_rock() {
+        let e = parse("O.#\n.@.\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 14, line 2, column 2: expected '.', '#' or 'O', found \"@\""
+        );
     }
 
     #[test]
     fn example2() {
-        let map1 = std::fs::read("res/day14/cycle1.txt").unwrap();
-        let map2 = std::fs::read("res/day14/cycle2.txt").unwrap();
-        let map3 = std::fs::read("res/day14/cycle3.txt").unwrap();
+        let map1 = parse(include_str!("../res/day14/cycle1.txt")).unwrap();
+        let map2 = parse(include_str!("../res/day14/cycle2.txt")).unwrap();
+        let map3 = parse(include_str!("../res/day14/cycle3.txt")).unwrap();
 
-        let path = Path::new("res/day14/example.txt");
-        let mut map = Part2::new(path);
+        let mut map = Part2::of(parse(EXAMPLE).unwrap());
         map.cycle();
-        assert_eq!(&map.bytes, &map1);
+        assert_eq!(map.grid, map1);
         map.cycle();
-        assert_eq!(&map.bytes, &map2);
+        assert_eq!(map.grid, map2);
         map.cycle();
-        assert_eq!(&map.bytes, &map3);
+        assert_eq!(map.grid, map3);
 
-        let mut map = Part2::new(path);
+        let mut map = Part2::of(parse(EXAMPLE).unwrap());
         let load = map.find_loop();
         assert_eq!(load, 64);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day14"), ignore = "res/day14/input.txt is missing")]
     fn part2() {
-        let mut map = Part2::new(Path::new("res/day14/input.txt"));
+        let mut map = Part2::new(Path::new("res/day14/input.txt")).unwrap();
         let load = map.find_loop();
-        assert_eq!(load, 91286);
+        assert_eq!(load.to_string(), expected(DAY, 2));
     }
 }
@@ -1,9 +1,87 @@
 use std::path::Path;
 
-pub fn run(path: &Path) -> usize {
-    let text = std::fs::read_to_string(path).unwrap();
-    let text = text.trim_end();
-    text.split(',').map(hash).map(usize::from).sum()
+use log::debug;
+
+use crate::{
+    error::{ParseError, Result},
+    solution::Solution,
+};
+
+const DAY: usize = 15;
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    type Input = Vec<Step>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        Ok(hash_sum(input))
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        Ok(focusing_power(input))
+    }
+}
+
+/// One step of the initialization sequence, e.g. `rn=1` or `cm-`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub text: String,
+    pub op: Op,
+    label_len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// `-`
+    Remove,
+    /// `=` and the focal length
+    Insert(u8),
+}
+
+impl Step {
+    pub fn label(&self) -> &str {
+        &self.text[..self.label_len]
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<Step>> {
+    text.trim_end()
+        .split(',')
+        .map(|cmd| {
+            let (label, op) = if let Some(label) = cmd.strip_suffix('-') {
+                (label, Op::Remove)
+            } else {
+                let (label, focal_length) = cmd
+                    .split_once('=')
+                    .ok_or_else(|| ParseError::at(DAY, text, cmd, "'-' or '='"))?;
+                let focal_length = focal_length
+                    .parse()
+                    .map_err(|_| ParseError::at(DAY, text, focal_length, "a focal length"))?;
+                (label, Op::Insert(focal_length))
+            };
+            Ok(Step {
+                text: cmd.to_owned(),
+                op,
+                label_len: label.len(),
+            })
+        })
+        .collect()
+}
+
+pub fn run(path: &Path) -> Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(hash_sum(&parse(&text)?))
+}
+
+pub fn hash_sum(steps: &[Step]) -> usize {
+    steps.iter().map(|step| usize::from(hash(&step.text))).sum()
 }
 
 pub fn hash(input: &str) -> u8 {
@@ -12,30 +90,32 @@ pub fn hash(input: &str) -> u8 {
         .fold(0, |a, b| a.wrapping_add(b).wrapping_mul(17))
 }
 
-pub fn run_part2(path: &Path) -> usize {
-    let text = std::fs::read_to_string(path).unwrap();
-    let text = text.trim_end();
+pub fn run_part2(path: &Path) -> Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(focusing_power(&parse(&text)?))
+}
+
+pub fn focusing_power(steps: &[Step]) -> usize {
     let mut map: [Vec<(&str, u8)>; 256] = std::array::from_fn(|_i| vec![]);
-    for cmd in text.split(',') {
-        if let Some(label) = cmd.strip_suffix('-') {
-            let _box = hash(label);
-            let _box = &mut map[_box as usize];
-            if let Some(pos) = _box.iter().position(|x| x.0 == label) {
-                _box.remove(pos);
+    for step in steps {
+        let label = step.label();
+        let _box = &mut map[hash(label) as usize];
+        match step.op {
+            Op::Remove => {
+                if let Some(pos) = _box.iter().position(|x| x.0 == label) {
+                    _box.remove(pos);
+                }
             }
-        } else {
-            let (label, focal_length) = cmd.split_once('=').unwrap();
-            let _box = hash(label);
-            let _box = &mut map[_box as usize];
-            let focal_length: u8 = focal_length.parse().unwrap();
-            if let Some((_, f)) = _box.iter_mut().find(|x| x.0 == label) {
-                *f = focal_length;
-            } else {
-                _box.push((label, focal_length));
+            Op::Insert(focal_length) => {
+                if let Some((_, f)) = _box.iter_mut().find(|x| x.0 == label) {
+                    *f = focal_length;
+                } else {
+                    _box.push((label, focal_length));
+                }
             }
         }
     }
-    eprintln!("{:?}", map);
+    debug!("{:?}", map);
     map.iter()
         .enumerate()
         .map(|(bi, contents)| {
@@ -53,25 +133,41 @@ pub fn run_part2(path: &Path) -> usize {
 mod tests {
     use std::path::Path;
 
-    use super::{run, run_part2};
+    use super::{focusing_power, hash_sum, parse, run, run_part2, DAY};
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day15/example.txt");
 
     #[test]
     fn example1() {
-        assert_eq!(run(Path::new("res/day15/example.txt")), 1320);
+        assert_eq!(hash_sum(&parse(EXAMPLE).unwrap()), 1320);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day15"), ignore = "res/day15/input.txt is missing")]
     fn part1() {
-        assert_eq!(run(Path::new("res/day15/input.txt")), 503154);
+        let sum = run(Path::new("res/day15/input.txt")).unwrap();
+        assert_eq!(sum.to_string(), expected(DAY, 1));
     }
 
     #[test]
     fn example2() {
-        assert_eq!(run_part2(Path::new("res/day15/example.txt")), 145);
+        assert_eq!(focusing_power(&parse(EXAMPLE).unwrap()), 145);
+    }
+
+    #[test]
+    fn invalid_step() {
+        let e = parse("rn=1,cm-,qp=x\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 15, line 1, column 13: expected a focal length, found \"x\""
+        );
     }
 
     #[test]
+    #[cfg_attr(not(input = "day15"), ignore = "res/day15/input.txt is missing")]
     fn part2() {
-        assert_eq!(run_part2(Path::new("res/day15/input.txt")), 251353);
+        let power = run_part2(Path::new("res/day15/input.txt")).unwrap();
+        assert_eq!(power.to_string(), expected(DAY, 2));
     }
 }
@@ -1,5 +1,38 @@
 use std::{collections::BTreeSet, path::Path};
 
+use log::debug;
+
+use crate::{error::Result, solution::Solution, util::Grid};
+
+const DAY: usize = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tile {
+    /// `.`
+    Empty,
+    /// `/`
+    Slash,
+    /// `\\`
+    Backslash,
+    /// `|`
+    Vertical,
+    /// `-`
+    Horizontal,
+}
+
+impl Tile {
+    fn of_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Self::Empty),
+            '/' => Some(Self::Slash),
+            '\\' => Some(Self::Backslash),
+            '|' => Some(Self::Vertical),
+            '-' => Some(Self::Horizontal),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum FromDir {
     Top,
@@ -12,7 +45,7 @@ struct ToDo {
     width: usize,
     height: usize,
     map: BTreeSet<(usize, usize, FromDir)>,
-    visited: BTreeSet<(usize, FromDir)>,
+    visited: BTreeSet<Enter>,
 }
 
 type Enter = (usize, usize, FromDir);
@@ -56,33 +89,64 @@ impl ToDo {
     }
 }
 
-pub fn run(path: &Path) -> usize {
-    let text = std::fs::read_to_string(path).unwrap();
-    let width = text.split_once('\n').unwrap().0.len();
-    let stride = width + 1;
-    let height = text.len() / stride;
+pub struct Day16;
 
-    energize(&text, height, stride, width, (0, 0, FromDir::Left))
+impl Solution for Day16 {
+    type Input = Grid<Tile>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        Ok(energized(input))
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        Ok(max_energized(input))
+    }
 }
 
-pub fn run_part2(path: &Path) -> usize {
-    let text = std::fs::read_to_string(path).unwrap();
-    let width = text.split_once('\n').unwrap().0.len();
-    let stride = width + 1;
-    let height = text.len() / stride;
+pub fn parse(text: &str) -> Result<Grid<Tile>> {
+    Ok(Grid::parse(
+        DAY,
+        text,
+        "a mirror, splitter or '.'",
+        Tile::of_char,
+    )?)
+}
+
+pub fn run(path: &Path) -> Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(energized(&parse(&text)?))
+}
+
+pub fn energized(grid: &Grid<Tile>) -> usize {
+    energize(grid, (0, 0, FromDir::Left))
+}
+
+pub fn run_part2(path: &Path) -> Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(max_energized(&parse(&text)?))
+}
+
+pub fn max_energized(grid: &Grid<Tile>) -> usize {
+    let (width, height) = (grid.width(), grid.height());
 
     let mut max = 0;
     let mut start = (0, 0, FromDir::Left);
 
     for y in 0..height {
         let s = (0, y, FromDir::Left);
-        let m = energize(&text, height, stride, width, s);
+        let m = energize(grid, s);
         if m > max {
             max = m;
             start = s;
         }
         let s = (width - 1, y, FromDir::Right);
-        let m = energize(&text, height, stride, width, s);
+        let m = energize(grid, s);
         if m > max {
             max = m;
             start = s;
@@ -90,86 +154,95 @@ pub fn run_part2(path: &Path) -> usize {
     }
     for x in 0..width {
         let s = (x, 0, FromDir::Top);
-        let m = energize(&text, height, stride, width, s);
+        let m = energize(grid, s);
         if m > max {
             max = m;
             start = s;
         }
         let s = (x, height - 1, FromDir::Bottom);
-        let m = energize(&text, height, stride, width, s);
+        let m = energize(grid, s);
         if m > max {
             max = m;
             start = s;
         }
     }
-    eprintln!("{start:?}");
+    debug!("best start: {start:?}");
     max
 }
 
-fn energize(text: &str, height: usize, stride: usize, width: usize, start: Enter) -> usize {
-    let mut energized = vec![b'.'; text.len()];
-    for y in 0..height {
-        energized[y * stride + width] = b'\n';
-    }
-    let mut todo = ToDo::new(width, height, start);
+fn energize(grid: &Grid<Tile>, start: Enter) -> usize {
+    use Tile::*;
+    let mut energized = grid.map(|_| false);
+    let mut todo = ToDo::new(grid.width(), grid.height(), start);
     while let Some((x, y, from)) = todo.pop() {
-        let index = y * stride + x;
-        if todo.visited.insert((index, from)) {
-            energized[index] = b'#';
-            match (from, text.as_bytes()[index]) {
-                (FromDir::Left | FromDir::Right, b'|') => {
+        if todo.visited.insert((x, y, from)) {
+            energized[(x, y)] = true;
+            match (from, grid[(x, y)]) {
+                (FromDir::Left | FromDir::Right, Vertical) => {
                     todo.up(x, y);
                     todo.down(x, y);
                 }
-                (FromDir::Top | FromDir::Bottom, b'-') => {
+                (FromDir::Top | FromDir::Bottom, Horizontal) => {
                     todo.left(x, y);
                     todo.right(x, y);
                 }
-                (FromDir::Left, b'/')
-                | (FromDir::Right, b'\\')
-                | (FromDir::Bottom, b'.' | b'|') => todo.up(x, y),
-                (FromDir::Right, b'/') | (FromDir::Left, b'\\') | (FromDir::Top, b'.' | b'|') => {
-                    todo.down(x, y)
-                }
-                (FromDir::Top, b'/') | (FromDir::Bottom, b'\\') | (FromDir::Right, b'.' | b'-') => {
-                    todo.left(x, y)
-                }
-                (FromDir::Bottom, b'/') | (FromDir::Top, b'\\') | (FromDir::Left, b'.' | b'-') => {
-                    todo.right(x, y)
-                }
-                (_, c) => panic!("{c}"),
+                (FromDir::Left, Slash)
+                | (FromDir::Right, Backslash)
+                | (FromDir::Bottom, Empty | Vertical) => todo.up(x, y),
+                (FromDir::Right, Slash)
+                | (FromDir::Left, Backslash)
+                | (FromDir::Top, Empty | Vertical) => todo.down(x, y),
+                (FromDir::Top, Slash)
+                | (FromDir::Bottom, Backslash)
+                | (FromDir::Right, Empty | Horizontal) => todo.left(x, y),
+                (FromDir::Bottom, Slash)
+                | (FromDir::Top, Backslash)
+                | (FromDir::Left, Empty | Horizontal) => todo.right(x, y),
             }
         }
     }
-    //std::io::Write::write_all(&mut std::io::stderr(), &energized).unwrap();
-    energized.iter().copied().filter(|c| *c == b'#').count()
+    energized.iter().filter(|(_, e)| **e).count()
 }
 
 #[cfg(test)]
 mod tests {
     use std::path::Path;
 
-    use super::{run, run_part2};
+    use super::{energized, max_energized, parse, run, run_part2, DAY};
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day16/example.txt");
 
     #[test]
     fn example1() {
-        assert_eq!(run(Path::new("res/day16/example.txt")), 46);
+        assert_eq!(energized(&parse(EXAMPLE).unwrap()), 46);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day16"), ignore = "res/day16/input.txt is missing")]
     fn part1() {
-        let e = run(Path::new("res/day16/input.txt"));
-        assert!(e > 6930);
-        assert_eq!(e, 8098);
+        let e = run(Path::new("res/day16/input.txt")).unwrap();
+        assert_eq!(e.to_string(), expected(DAY, 1));
+    }
+
+    #[test]
+    fn invalid_tile() {
+        let e = parse(".|.\n.+.\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 16, line 2, column 2: expected a mirror, splitter or '.', found \"+\""
+        );
     }
 
     #[test]
     fn example2() {
-        assert_eq!(run_part2(Path::new("res/day16/example.txt")), 51);
+        assert_eq!(max_energized(&parse(EXAMPLE).unwrap()), 51);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day16"), ignore = "res/day16/input.txt is missing")]
     fn part2() {
-        assert_eq!(run_part2(Path::new("res/day16/input.txt")), 8335);
+        let e = run_part2(Path::new("res/day16/input.txt")).unwrap();
+        assert_eq!(e.to_string(), expected(DAY, 2));
     }
 }
@@ -4,7 +4,39 @@ use std::{
     path::Path,
 };
 
-use crate::util::{Direction, Map, MapDimensions};
+use crate::{
+    error::{Error, Result},
+    solution::Solution,
+    util::{Direction, Grid, MapDimensions},
+};
+
+const DAY: usize = 17;
+
+pub struct Day17;
+
+impl Solution for Day17 {
+    type Input = Grid<u8>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        min_heat_loss(input, false)
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        min_heat_loss(input, true)
+    }
+}
+
+/// Parses a map of heat loss digits
+pub fn parse(text: &str) -> Result<Grid<u8>> {
+    let digit = |c: char| c.to_digit(10).map(|d| d as u8);
+    Ok(Grid::parse(DAY, text, "a digit", digit)?)
+}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Node {
@@ -38,7 +70,7 @@ impl Node {
 }
 
 type UpdateFn = fn(
-    &Map,
+    &Grid<u8>,
     Direction,
     (usize, usize),
     usize,
@@ -47,15 +79,22 @@ type UpdateFn = fn(
     &mut BinaryHeap<(Reverse<usize>, usize)>,
 );
 
-pub fn run(path: &Path, is_part2: bool) -> usize {
+pub fn run(path: &Path, is_part2: bool) -> Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    min_heat_loss(&parse(&text)?, is_part2)
+}
+
+pub fn min_heat_loss(map: &Grid<u8>, is_part2: bool) -> Result<usize> {
     let update: UpdateFn = match is_part2 {
         false => update_part1,
         true => update_part2,
     };
-    let map = Map::open(path).unwrap();
-    let dim = map.dim();
-    let w1 = map.ascii_num_at((1, 0));
-    let w2 = map.ascii_num_at((0, 1));
+    let dim = &map.dim();
+    if dim.width() < 2 || dim.height() < 2 {
+        return Err(Error::unsolvable(DAY, "map is too small"));
+    }
+    let w1 = map[(1, 0)];
+    let w2 = map[(0, 1)];
     let mut seen = BTreeSet::new();
     use Direction::*;
     let mut todo = BinaryHeap::<(Reverse<usize>, usize)>::from([
@@ -70,17 +109,16 @@ pub fn run(path: &Path, is_part2: bool) -> usize {
             dir,
             count,
         } = Node::unpack(dim, node);
-        //eprintln!("({x},{y}) {dir:?}({count}) => {d}");
         if (x, y) == target && (!is_part2 || count >= 4) {
-            return d;
+            return Ok(d);
         }
-        update(&map, dir, (x, y), d, count, &mut seen, &mut todo);
+        update(map, dir, (x, y), d, count, &mut seen, &mut todo);
     }
-    panic!("Search failed")
+    Err(Error::unsolvable(DAY, "no path to the factory"))
 }
 
 pub fn update_part2(
-    map: &Map,
+    map: &Grid<u8>,
     dir: Direction,
     (x, y): (usize, usize),
     d: usize,
@@ -88,7 +126,7 @@ pub fn update_part2(
     seen: &mut BTreeSet<usize>,
     todo: &mut BinaryHeap<(Reverse<usize>, usize)>,
 ) {
-    let dim = map.dim();
+    let dim = &map.dim();
     if cnt >= 4 {
         if let Some((dir, (x1, y1))) = dim.turn_left(dir, (x, y)) {
             update(map, (x1, y1), d, dir, 1, seen, todo);
@@ -105,7 +143,7 @@ pub fn update_part2(
 }
 
 pub fn update_part1(
-    map: &Map,
+    map: &Grid<u8>,
     dir: Direction,
     (x, y): (usize, usize),
     d: usize,
@@ -113,7 +151,7 @@ pub fn update_part1(
     seen: &mut BTreeSet<usize>,
     todo: &mut BinaryHeap<(Reverse<usize>, usize)>,
 ) {
-    let dim = map.dim();
+    let dim = &map.dim();
     if let Some((dir, (x1, y1))) = dim.turn_left(dir, (x, y)) {
         update(map, (x1, y1), d, dir, 1, seen, todo);
     }
@@ -128,7 +166,7 @@ pub fn update_part1(
 }
 
 fn update(
-    map: &Map,
+    map: &Grid<u8>,
     (x1, y1): (usize, usize),
     d: usize,
     dir: Direction,
@@ -136,8 +174,8 @@ fn update(
     seen: &mut BTreeSet<usize>,
     todo: &mut BinaryHeap<(Reverse<usize>, usize)>,
 ) {
-    let w = map.ascii_num_at((x1, y1)) as usize;
-    let p = Node::new((x1, y1), dir, count).pack(map.dim());
+    let w = map[(x1, y1)] as usize;
+    let p = Node::new((x1, y1), dir, count).pack(&map.dim());
     let d2 = d + w; // New weight
     if seen.insert(p) {
         todo.push((Reverse(d2), p));
@@ -148,9 +186,13 @@ fn update(
 mod tests {
     use std::path::Path;
 
-    use super::{run, Node};
+    use super::{min_heat_loss, parse, run, Node, DAY};
+    use crate::answers::expected;
     use crate::util::{Direction::Down, MapDimensions};
 
+    const EXAMPLE: &str = include_str!("../res/day17/example.txt");
+    const EXAMPLE2: &str = include_str!("../res/day17/example2.txt");
+
     #[test]
     fn test_pack() {
         let dim = MapDimensions::new(100, 100);
@@ -165,26 +207,39 @@ mod tests {
 
     #[test]
     fn example1() {
-        assert_eq!(run(Path::new("res/day17/example.txt"), false), 102);
+        assert_eq!(min_heat_loss(&parse(EXAMPLE).unwrap(), false).unwrap(), 102);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day17"), ignore = "res/day17/input.txt is missing")]
     fn part1() {
-        assert_eq!(run(Path::new("res/day17/input.txt"), false), 907);
+        let loss = run(Path::new("res/day17/input.txt"), false).unwrap();
+        assert_eq!(loss.to_string(), expected(DAY, 1));
+    }
+
+    #[test]
+    fn invalid_digit() {
+        let e = parse("12\n3x\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 17, line 2, column 2: expected a digit, found \"x\""
+        );
     }
 
     #[test]
     fn example2() {
-        assert_eq!(run(Path::new("res/day17/example.txt"), true), 94);
+        assert_eq!(min_heat_loss(&parse(EXAMPLE).unwrap(), true).unwrap(), 94);
     }
 
     #[test]
     fn example2b() {
-        assert_eq!(run(Path::new("res/day17/example2.txt"), true), 71);
+        assert_eq!(min_heat_loss(&parse(EXAMPLE2).unwrap(), true).unwrap(), 71);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day17"), ignore = "res/day17/input.txt is missing")]
     fn part2() {
-        assert_eq!(run(Path::new("res/day17/input.txt"), true), 1057);
+        let loss = run(Path::new("res/day17/input.txt"), true).unwrap();
+        assert_eq!(loss.to_string(), expected(DAY, 2));
     }
 }
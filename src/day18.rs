@@ -1,51 +1,91 @@
 use std::path::Path;
 
-use regex::Regex;
-
-use crate::util::{
-    area::{area, Edge, EdgeMap, Noop},
-    Direction,
+use log::{debug, trace};
+use regex::{Captures, Regex};
+
+use crate::{
+    error::{Error, ParseError, Result},
+    solution::Solution,
+    util::{
+        area::{area, Edge, EdgeMap, Noop},
+        Direction,
+    },
 };
 
-pub fn parse_input_part1(text: &str) -> Vec<(Direction, usize)> {
-    let regex = Regex::new(r"^([UDLR]) (\d+) \(#([0-9a-f]{6})\)$").unwrap();
-    text.lines()
-        .filter(|l| !l.is_empty())
-        .map(|line| {
-            let c = regex.captures(line).unwrap();
-            let dir = match c.get(1).unwrap().as_str() {
-                "U" => Direction::Up,
-                "D" => Direction::Down,
-                "L" => Direction::Left,
-                "R" => Direction::Right,
-                _ => panic!(),
-            };
-            let len: usize = c.get(2).unwrap().as_str().parse().unwrap();
-            //let color_hex = c.get(3).unwrap().as_str();
-            //let color = u32::from_str_radix(color_hex, 16).unwrap();
-            (dir, len)
-        })
-        .collect::<Vec<_>>()
+const DAY: usize = 18;
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    /// The dig plans as read for part 1 and part 2
+    type Input = (Vec<(Direction, usize)>, Vec<(Direction, usize)>);
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        Ok((parse_input_part1(text)?, parse_input_part2(text)?))
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        lagoon_part1(&input.0)
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        lagoon_part2(&input.1)
+    }
 }
 
-pub fn parse_input_part2(text: &str) -> Vec<(Direction, usize)> {
+fn parse_plan(
+    text: &str,
+    step: impl Fn(&Captures) -> Result<(Direction, usize), ParseError>,
+) -> Result<Vec<(Direction, usize)>> {
     let regex = Regex::new(r"^([UDLR]) (\d+) \(#([0-9a-f]{6})\)$").unwrap();
     text.lines()
         .filter(|l| !l.is_empty())
         .map(|line| {
-            let c = regex.captures(line).unwrap();
-            let hex_str = c.get(3).unwrap().as_str();
-            let len = usize::from_str_radix(&hex_str[..5], 16).unwrap();
-            let dir = match hex_str.as_bytes()[5] {
-                b'0' => Direction::Right,
-                b'1' => Direction::Down,
-                b'2' => Direction::Left,
-                b'3' => Direction::Up,
-                _ => panic!(),
-            };
-            (dir, len)
+            let c = regex
+                .captures(line)
+                .ok_or_else(|| ParseError::at(DAY, text, line, "a dig instruction"))?;
+            Ok(step(&c)?)
         })
-        .collect::<Vec<_>>()
+        .collect()
+}
+
+pub fn parse_input_part1(text: &str) -> Result<Vec<(Direction, usize)>> {
+    parse_plan(text, |c| {
+        let dir = match &c[1] {
+            "U" => Direction::Up,
+            "D" => Direction::Down,
+            "L" => Direction::Left,
+            "R" => Direction::Right,
+            _ => unreachable!(),
+        };
+        let len = c.get(2).unwrap().as_str();
+        let len: usize = len
+            .parse()
+            .map_err(|_| ParseError::at(DAY, text, len, "a length"))?;
+        //let color_hex = c.get(3).unwrap().as_str();
+        //let color = u32::from_str_radix(color_hex, 16).unwrap();
+        Ok((dir, len))
+    })
+}
+
+pub fn parse_input_part2(text: &str) -> Result<Vec<(Direction, usize)>> {
+    parse_plan(text, |c| {
+        let hex_str = c.get(3).unwrap().as_str();
+        let len = usize::from_str_radix(&hex_str[..5], 16).unwrap();
+        let dir = match hex_str.as_bytes()[5] {
+            b'0' => Direction::Right,
+            b'1' => Direction::Down,
+            b'2' => Direction::Left,
+            b'3' => Direction::Up,
+            _ => {
+                let digit = &hex_str[5..];
+                return Err(ParseError::at(DAY, text, digit, "a direction 0-3"));
+            }
+        };
+        Ok((dir, len))
+    })
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -54,18 +94,28 @@ enum Turn {
     Left,
 }
 
-pub fn run_part2(path: &Path) -> usize {
-    let text = std::fs::read_to_string(path).unwrap();
-    let input = parse_input_part2(&text);
+pub fn run_part2(path: &Path) -> Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    lagoon_part2(&parse_input_part2(&text)?)
+}
 
+pub fn lagoon_part2(input: &[(Direction, usize)]) -> Result<usize> {
+    if input.len() < 4 {
+        return Err(Error::unsolvable(DAY, "dig plan is too short"));
+    }
     let mut turns = Vec::with_capacity(input.len());
     let mut prev_dir = input.last().unwrap().0;
-    for (dir, count) in input {
+    for &(dir, count) in input {
         use Direction::*;
         let turn = match (prev_dir, dir) {
             (Right, Up) | (Up, Left) | (Left, Down) | (Down, Right) => Turn::Left,
             (Right, Down) | (Up, Right) | (Left, Up) | (Down, Left) => Turn::Right,
-            _ => panic!("{prev_dir:?}=>{dir:?}"),
+            _ => {
+                return Err(Error::unsolvable(
+                    DAY,
+                    "dig plan does not turn at every step",
+                ))
+            }
         };
         turns.push((turn, count));
         prev_dir = dir;
@@ -102,39 +152,43 @@ pub fn run_part2(path: &Path) -> usize {
     let mut balance = 0isize;
 
     while turns.len() > 4 {
-        eprintln!("1: {turns:?}");
+        trace!("1: {turns:?}");
         while let Some(next) = find_next(&turns, is_in_out_in) {
             let (b, c) = update1(&mut turns, next); // the resulting shape is too big
             balance -= (b * c) as isize;
         }
 
-        eprintln!("2: {turns:?}");
+        trace!("2: {turns:?}");
         while let Some(next) = find_next(&turns, is_out_in_in_gt) {
             let (b, c) = update2(&mut turns, next); // the resulting shape is too small;
             balance += (b * (c + 1)) as isize;
         }
 
-        eprintln!("3: {turns:?}");
+        trace!("3: {turns:?}");
         while let Some(next) = find_next(&turns, is_out_in_out) {
             let (b, c) = update1(&mut turns, next); // the resulting shape is too small;
             balance += (b * c) as isize;
         }
 
-        eprintln!("4: {turns:?}");
+        trace!("4: {turns:?}");
         while let Some(next) = find_next(&turns, is_in_out_out_gt) {
             let (b, c) = update2(&mut turns, next); // the resulting shape is too big
             balance -= (b * (c - 1)) as isize;
         }
-        eprintln!("balance: {balance}");
+        trace!("balance: {balance}");
+    }
+    debug!("end: {turns:?}");
+
+    if turns.len() != 4
+        || !turns.iter().copied().all(|(turn, _)| turn == turn_in)
+        || turns[0].1 != turns[2].1
+        || turns[1].1 != turns[3].1
+    {
+        return Err(Error::unsolvable(DAY, "dig plan is not a closed loop"));
     }
-    eprintln!("end: {turns:?}");
-
-    assert!(turns.iter().copied().all(|(turn, _)| turn == turn_in));
-    assert_eq!(turns[0].1, turns[2].1);
-    assert_eq!(turns[1].1, turns[3].1);
 
     balance += ((turns[0].1 + 1) * (turns[1].1 + 1)) as isize;
-    balance as usize
+    Ok(balance as usize)
 }
 
 fn update1(turns: &mut Vec<(Turn, usize)>, next: usize) -> (usize, usize) {
@@ -165,7 +219,7 @@ fn remove_two(turns: &mut Vec<(Turn, usize)>, b: usize, c: usize) {
     }
 }
 
-fn get_indices(turns: &mut Vec<(Turn, usize)>, next: usize) -> (usize, usize, usize, usize) {
+fn get_indices(turns: &mut [(Turn, usize)], next: usize) -> (usize, usize, usize, usize) {
     let len = turns.len();
     let a = (next + (len - 1)) % len;
     let b = next;
@@ -194,15 +248,22 @@ fn find_next(
         .position(is_out_in_in)
 }
 
-pub fn run_part1(path: &Path) -> usize {
-    let text = std::fs::read_to_string(path).unwrap();
-    let input = parse_input_part1(&text);
+pub fn run_part1(path: &Path) -> Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    lagoon_part1(&parse_input_part1(&text)?)
+}
 
+pub fn lagoon_part1(input: &[(Direction, usize)]) -> Result<usize> {
     let mut edges = EdgeMap::<isize>::new();
     let mut pos = (0isize, 0isize);
-    let mut prev_dir = input.last().map(|f| f.0).unwrap();
-    for (dir, cnt) in input {
-        edges.insert(pos, Edge::of_dir_pair(prev_dir, dir).unwrap());
+    let mut prev_dir = input
+        .last()
+        .map(|f| f.0)
+        .ok_or_else(|| Error::unsolvable(DAY, "dig plan is too short"))?;
+    for &(dir, cnt) in input {
+        let edge = Edge::of_dir_pair(prev_dir, dir)
+            .ok_or_else(|| Error::unsolvable(DAY, "dig plan does not turn at every step"))?;
+        edges.insert(pos, edge);
         match dir {
             Direction::Right => {
                 let edge = Edge::EastWest;
@@ -256,38 +317,51 @@ pub fn run_part1(path: &Path) -> usize {
 
     let count_inner = area(min.0..max.0, min.1..max.1, &edges, &mut Noop);
 
-    count_inner + edges.len()
+    Ok(count_inner + edges.len())
 }
 
 #[cfg(test)]
 mod tests {
     use std::path::Path;
 
+    use super::{lagoon_part1, lagoon_part2, parse_input_part1, parse_input_part2, DAY};
     use super::{run_part1, run_part2};
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day18/example.txt");
 
     #[test]
     fn example1() {
-        assert_eq!(run_part1(Path::new("res/day18/example.txt")), 62);
+        let plan = parse_input_part1(EXAMPLE).unwrap();
+        assert_eq!(lagoon_part1(&plan).unwrap(), 62);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day18"), ignore = "res/day18/input.txt is missing")]
     fn part1() {
-        let count = run_part1(Path::new("res/day18/input.txt"));
-        assert_ne!(count, 42590);
-        assert_eq!(count, 42317);
+        let count = run_part1(Path::new("res/day18/input.txt")).unwrap();
+        assert_eq!(count.to_string(), expected(DAY, 1));
+    }
+
+    #[test]
+    fn invalid_color() {
+        let e = parse_input_part2("R 6 (#70c710)\nD 5 (#0dc574)\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 18, line 2, column 12: expected a direction 0-3, found \"4\""
+        );
     }
 
     #[test]
     fn example2() {
-        let count = run_part2(Path::new("res/day18/example.txt"));
+        let count = lagoon_part2(&parse_input_part2(EXAMPLE).unwrap()).unwrap();
         assert_eq!(count, 952408144115);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day18"), ignore = "res/day18/input.txt is missing")]
     fn part2() {
-        let count = run_part2(Path::new("res/day18/input.txt"));
-        assert_ne!(count, 83605485349422);
-        assert!(count > 83605514877556, "answer too low");
-        assert_eq!(count, 83605563360288);
+        let count = run_part2(Path::new("res/day18/input.txt")).unwrap();
+        assert_eq!(count.to_string(), expected(DAY, 2));
     }
 }
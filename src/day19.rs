@@ -1,5 +1,34 @@
 use std::{collections::BTreeMap, ops::Range, path::Path, str::Split};
 
+use log::{debug, trace};
+
+use crate::{
+    error::{Error, ParseError, Result},
+    solution::Solution,
+};
+
+const DAY: usize = 19;
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    type Input = System;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        input.accepted_sum()
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        input.accepted_combinations()
+    }
+}
+
 #[derive(Debug)]
 pub struct Item {
     pub x: usize,
@@ -36,13 +65,13 @@ pub enum Field {
 }
 
 impl Field {
-    fn of_char(c: char) -> Self {
+    fn of_char(c: char) -> Option<Self> {
         match c {
-            'x' => Self::X,
-            'm' => Self::M,
-            'a' => Self::A,
-            's' => Self::S,
-            _ => panic!(),
+            'x' => Some(Self::X),
+            'm' => Some(Self::M),
+            'a' => Some(Self::A),
+            's' => Some(Self::S),
+            _ => None,
         }
     }
 
@@ -63,11 +92,11 @@ pub enum Op {
 }
 
 impl Op {
-    fn of_char(c: char) -> Self {
+    fn of_char(c: char) -> Option<Self> {
         match c {
-            '<' => Self::Lt,
-            '>' => Self::Gt,
-            _ => panic!(),
+            '<' => Some(Self::Lt),
+            '>' => Some(Self::Gt),
+            _ => None,
         }
     }
 
@@ -124,13 +153,21 @@ impl ValidRanges {
         }
     }
 
-    pub fn count(&self, transitions: &BTreeMap<usize, State>, state: usize) -> usize {
+    /// Combinations accepted from `state`, at most `depth` rules away from
+    /// a decision
+    pub fn count(
+        &self,
+        transitions: &BTreeMap<usize, State>,
+        state: usize,
+        depth: usize,
+    ) -> Result<usize> {
         if state == REJECT {
-            0
+            Ok(0)
         } else if state == ACCEPT {
-            self.x.len() * self.m.len() * self.a.len() * self.s.len()
+            Ok(self.x.len() * self.m.len() * self.a.len() * self.s.len())
         } else {
-            let t = transitions.get(&state).unwrap();
+            let t = transition(transitions, state)?;
+            let depth = depth.checked_sub(1).ok_or_else(looping)?;
             let Range { start, end } = match t.field {
                 Field::X => &self.x,
                 Field::M => &self.m,
@@ -141,32 +178,38 @@ impl ValidRanges {
                 Op::Gt => {
                     let mid = t.operand + 1; // first match for x > operand
                     if mid >= *end {
-                        self.count(transitions, t.case_false)
+                        self.count(transitions, t.case_false, depth)
                     } else if *start > t.operand {
-                        self.count(transitions, t.case_true)
+                        self.count(transitions, t.case_true, depth)
                     } else {
-                        let a = self
-                            .with(t.field, *start..mid)
-                            .count(transitions, t.case_false);
-                        let b = self
-                            .with(t.field, mid..*end)
-                            .count(transitions, t.case_true);
-                        a + b
+                        let a = self.with(t.field, *start..mid).count(
+                            transitions,
+                            t.case_false,
+                            depth,
+                        )?;
+                        let b =
+                            self.with(t.field, mid..*end)
+                                .count(transitions, t.case_true, depth)?;
+                        Ok(a + b)
                     }
                 }
                 Op::Lt => {
                     if t.operand <= *start {
-                        self.count(transitions, t.case_false)
+                        self.count(transitions, t.case_false, depth)
                     } else if *end <= t.operand {
-                        self.count(transitions, t.case_true)
+                        self.count(transitions, t.case_true, depth)
                     } else {
-                        let a = self
-                            .with(t.field, *start..t.operand)
-                            .count(transitions, t.case_true);
-                        let b = self
-                            .with(t.field, t.operand..*end)
-                            .count(transitions, t.case_false);
-                        a + b
+                        let a = self.with(t.field, *start..t.operand).count(
+                            transitions,
+                            t.case_true,
+                            depth,
+                        )?;
+                        let b = self.with(t.field, t.operand..*end).count(
+                            transitions,
+                            t.case_false,
+                            depth,
+                        )?;
+                        Ok(a + b)
                     }
                 }
             }
@@ -185,157 +228,282 @@ impl ValidRanges {
     }
 }
 
-pub fn run(path: &Path) -> (usize, usize) {
-    let text = std::fs::read_to_string(path).unwrap();
-    let (_state_strs, item_strs) = text.split_once("\n\n").unwrap();
-    let items = parse_items(item_strs);
-    let (in_state, transitions) = parse_states(_state_strs);
-    eprintln!("initial state: {:#?}", in_state);
-    eprintln!("#transitions: {}", transitions.len());
-
-    // Part 1
-    let mut sum = 0;
-    for item in items {
-        eprintln!("{item:?}");
-        if apply(&transitions, in_state, &item) {
-            sum += item.sum();
+/// The parsed workflows and part ratings
+pub struct System {
+    in_state: usize,
+    transitions: BTreeMap<usize, State>,
+    items: Vec<Item>,
+}
+
+impl System {
+    /// Part 1
+    pub fn accepted_sum(&self) -> Result<usize> {
+        let mut sum = 0;
+        for item in &self.items {
+            trace!("{item:?}");
+            if apply(&self.transitions, self.in_state, item)? {
+                sum += item.sum();
+            }
         }
+        Ok(sum)
     }
 
-    // Part 2
-    let count = ValidRanges::new().count(&transitions, in_state);
+    /// Part 2
+    pub fn accepted_combinations(&self) -> Result<usize> {
+        let depth = self.transitions.len();
+        ValidRanges::new().count(&self.transitions, self.in_state, depth)
+    }
+}
 
-    (sum, count)
+pub fn run(path: &Path) -> Result<(usize, usize)> {
+    let text = std::fs::read_to_string(path)?;
+    let system = parse(&text)?;
+    Ok((system.accepted_sum()?, system.accepted_combinations()?))
 }
 
-fn apply(transitions: &BTreeMap<usize, State>, mut state: usize, item: &Item) -> bool {
-    while let Some(t) = transitions.get(&state) {
-        let new_state = t.apply(item);
-        eprintln!("{t:?} => {new_state}");
-        state = new_state;
-    }
-    match state {
-        ACCEPT => true,
-        REJECT => false,
-        _ => panic!("{state}"),
+pub fn parse(text: &str) -> Result<System> {
+    let (_state_strs, item_strs) = text
+        .split_once("\n\n")
+        .ok_or_else(|| ParseError::at(DAY, text, &text[text.len()..], "an empty line"))?;
+    let items = parse_items(text, item_strs)?;
+    let (in_state, transitions) = parse_states(text, _state_strs)?;
+    debug!("initial state: {:#?}", in_state);
+    debug!("#transitions: {}", transitions.len());
+    Ok(System {
+        in_state,
+        transitions,
+        items,
+    })
+}
+
+fn apply(transitions: &BTreeMap<usize, State>, mut state: usize, item: &Item) -> Result<bool> {
+    // without a loop, every rule is applied at most once
+    for _ in 0..=transitions.len() {
+        match state {
+            ACCEPT => return Ok(true),
+            REJECT => return Ok(false),
+            _ => {
+                let t = transition(transitions, state)?;
+                let new_state = t.apply(item);
+                trace!("{t:?} => {new_state}");
+                state = new_state;
+            }
+        }
     }
+    Err(looping())
+}
+
+fn transition(transitions: &BTreeMap<usize, State>, state: usize) -> Result<&State> {
+    transitions
+        .get(&state)
+        .ok_or_else(|| Error::unsolvable(DAY, format!("there is no rule {state}")))
+}
+
+fn looping() -> Error {
+    Error::unsolvable(DAY, "the workflows send parts around in a loop")
 }
 
-fn parse_states(_state_strs: &str) -> (usize, BTreeMap<usize, State>) {
+fn parse_states(text: &str, _state_strs: &str) -> Result<(usize, BTreeMap<usize, State>)> {
     const SUBSTATE_BITS: usize = 8;
     let state_lines = _state_strs
         .lines()
         .filter(|line| !line.is_empty())
         .map(|line| {
-            let (name, rest) = line.split_once('{').unwrap();
-            let def = rest.strip_suffix('}').unwrap();
-            (name, def)
+            line.split_once('{')
+                .and_then(|(name, rest)| Some((name, rest.strip_suffix('}')?)))
+                .ok_or_else(|| ParseError::at(DAY, text, line, "a workflow"))
         })
-        .collect::<BTreeMap<_, _>>();
-    let state_ids = state_lines
+        .collect::<Result<BTreeMap<_, _>, _>>()?;
+    let mut state_ids = state_lines
         .keys()
         .copied()
         .enumerate()
         .map(|(i, name)| (name, i << SUBSTATE_BITS))
         .collect::<BTreeMap<_, _>>();
-    let in_state = *state_ids.get("in").unwrap();
-    let transitions = state_lines
-        .into_iter()
-        .enumerate()
-        .flat_map(|(i, (name, def))| {
-            eprintln!("{name}: {i}");
-            let state_ids = &state_ids;
-            let mut iter = def.split(',');
-            let last = iter.next_back().unwrap();
-            let last = get_state_id(state_ids, last);
-            let mut iter = iter.enumerate().peekable();
-            std::iter::from_fn(move || {
-                if let Some((j, def)) = iter.next() {
-                    let state_id = (i << SUBSTATE_BITS) + j;
-                    let (cond, next) = def.split_once(':').unwrap();
-                    let case_true = get_state_id(state_ids, next);
-                    let case_false = if iter.peek().is_some() {
-                        state_id + 1
-                    } else {
-                        last
-                    };
-                    let mut cond_chars = cond.chars();
-                    let field = Field::of_char(cond_chars.next().unwrap());
-                    let op = Op::of_char(cond_chars.next().unwrap());
-                    let operand = cond_chars.as_str().parse().unwrap();
-                    Some((
-                        state_id,
-                        State {
-                            field,
-                            case_true,
-                            case_false,
-                            op,
-                            operand,
-                        },
-                    ))
-                } else {
-                    None
-                }
-            })
-        })
-        .collect();
-    (in_state, transitions)
+    // a workflow without conditions is just another name for its target
+    let is_alias = |def: &str| !def.contains(':');
+    for (&name, &def) in state_lines.iter().filter(|(_, def)| is_alias(def)) {
+        let mut target = def;
+        for _ in 0..state_lines.len() {
+            match state_lines.get(target) {
+                Some(&next) if is_alias(next) => target = next,
+                _ => break,
+            }
+        }
+        if state_lines.get(target).is_some_and(|&t| is_alias(t)) {
+            return Err(ParseError::at(DAY, text, def, "a workflow that leads somewhere").into());
+        }
+        let id = get_state_id(text, &state_ids, target)?;
+        state_ids.insert(name, id);
+    }
+    let in_state = *state_ids.get("in").ok_or_else(|| {
+        let end = &_state_strs[_state_strs.len()..];
+        ParseError::at(DAY, text, end, "a workflow named \"in\"")
+    })?;
+    let mut transitions = BTreeMap::new();
+    for (i, (name, def)) in state_lines.into_iter().enumerate() {
+        trace!("{name}: {i}");
+        let mut iter = def.split(',');
+        let last = iter.next_back().unwrap();
+        let last = get_state_id(text, &state_ids, last)?;
+        let mut iter = iter.enumerate().peekable();
+        while let Some((j, def)) = iter.next() {
+            let state_id = (i << SUBSTATE_BITS) + j;
+            let (cond, next) = def
+                .split_once(':')
+                .ok_or_else(|| ParseError::at(DAY, text, def, "a rule"))?;
+            let case_true = get_state_id(text, &state_ids, next)?;
+            let case_false = if iter.peek().is_some() {
+                state_id + 1
+            } else {
+                last
+            };
+            let mut cond_chars = cond.chars();
+            let field = cond_chars
+                .next()
+                .and_then(Field::of_char)
+                .ok_or_else(|| ParseError::at(DAY, text, cond, "one of x, m, a or s"))?;
+            let op = cond_chars
+                .next()
+                .and_then(Op::of_char)
+                .ok_or_else(|| ParseError::at(DAY, text, &cond[1..], "'<' or '>'"))?;
+            let operand = cond_chars.as_str();
+            let operand = operand
+                .parse()
+                .map_err(|_| ParseError::at(DAY, text, operand, "a number"))?;
+            transitions.insert(
+                state_id,
+                State {
+                    field,
+                    case_true,
+                    case_false,
+                    op,
+                    operand,
+                },
+            );
+        }
+    }
+    Ok((in_state, transitions))
 }
 
 const ACCEPT: usize = usize::MAX;
 const REJECT: usize = usize::MAX - 1;
 
-fn get_state_id(state_ids: &BTreeMap<&str, usize>, last: &str) -> usize {
+fn get_state_id(
+    text: &str,
+    state_ids: &BTreeMap<&str, usize>,
+    last: &str,
+) -> Result<usize, ParseError> {
     match last {
-        "A" => ACCEPT,
-        "R" => REJECT,
-        _ => *state_ids.get(last).unwrap(),
+        "A" => Ok(ACCEPT),
+        "R" => Ok(REJECT),
+        _ => state_ids
+            .get(last)
+            .copied()
+            .ok_or_else(|| ParseError::at(DAY, text, last, "a known workflow")),
     }
 }
 
-fn parse_items(item_strs: &str) -> Vec<Item> {
+fn parse_items(text: &str, item_strs: &str) -> Result<Vec<Item>> {
     item_strs
         .lines()
         .filter(|line| !line.is_empty())
         .map(|line| {
-            let line = line.strip_prefix('{').unwrap();
-            let line = line.strip_suffix('}').unwrap();
-            let mut iter = line.split(',');
-            let x = scan_line("x=", &mut iter);
-            let m = scan_line("m=", &mut iter);
-            let a = scan_line("a=", &mut iter);
-            let s = scan_line("s=", &mut iter);
-            Item { x, m, a, s }
+            let ratings = line
+                .strip_prefix('{')
+                .and_then(|line| line.strip_suffix('}'))
+                .ok_or_else(|| ParseError::at(DAY, text, line, "a part rating"))?;
+            let mut iter = ratings.split(',');
+            let mut scan = |prefix| scan_line(text, ratings, prefix, &mut iter);
+            let x = scan("x=")?;
+            let m = scan("m=")?;
+            let a = scan("a=")?;
+            let s = scan("s=")?;
+            Ok(Item { x, m, a, s })
         })
         .collect()
 }
 
-fn scan_line(prefix: &'static str, iter: &mut Split<'_, char>) -> usize {
-    iter.next()
-        .unwrap()
+fn scan_line(
+    text: &str,
+    line: &str,
+    prefix: &'static str,
+    iter: &mut Split<'_, char>,
+) -> Result<usize, ParseError> {
+    let rating = iter.next().unwrap_or(&line[line.len()..]);
+    rating
         .strip_prefix(prefix)
-        .unwrap()
-        .parse()
-        .unwrap()
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| ParseError::at(DAY, text, rating, format!("a rating {prefix}N")))
 }
 
 #[cfg(test)]
 mod tests {
     use std::path::Path;
 
-    use super::run;
+    use super::{parse, run, DAY};
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day19/example.txt");
 
     #[test]
     fn example() {
-        let (part1, part2) = run(Path::new("res/day19/example.txt"));
-        assert_eq!(part1, 19114);
-        assert_eq!(part2, 167409079868000);
+        let system = parse(EXAMPLE).unwrap();
+        assert_eq!(system.accepted_sum().unwrap(), 19114);
+        assert_eq!(system.accepted_combinations().unwrap(), 167409079868000);
+    }
+
+    #[test]
+    fn default_only() {
+        let system =
+            parse("in{x>1:px,qs}\npx{A}\nqs{R}\n\n{x=2,m=0,a=0,s=0}\n{x=1,m=0,a=0,s=0}\n").unwrap();
+        assert_eq!(system.accepted_sum().unwrap(), 2);
+        assert_eq!(
+            system.accepted_combinations().unwrap(),
+            3999 * 4000 * 4000 * 4000
+        );
+
+        let system = parse("in{qs}\nqs{px}\npx{x<3:A,R}\n\n{x=2,m=0,a=0,s=0}\n").unwrap();
+        assert_eq!(system.accepted_sum().unwrap(), 2);
+        assert_eq!(
+            system.accepted_combinations().unwrap(),
+            2 * 4000 * 4000 * 4000
+        );
+
+        let e = parse("in{qs}\nqs{in}\n\n{x=2,m=0,a=0,s=0}\n")
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.to_string(),
+            "day 19, line 1, column 4: expected a workflow that leads somewhere, found \"qs\""
+        );
+    }
+
+    #[test]
+    fn looping() {
+        let system = parse("in{x>1:px,A}\npx{m>1:in,R}\n\n{x=2,m=2,a=0,s=0}\n").unwrap();
+        let message = "day 19: the workflows send parts around in a loop";
+        assert_eq!(system.accepted_sum().unwrap_err().to_string(), message);
+        assert_eq!(
+            system.accepted_combinations().unwrap_err().to_string(),
+            message
+        );
+    }
+
+    #[test]
+    fn unknown_workflow() {
+        let e = parse("in{x>10:qq,A}\n\n{x=1,m=2,a=3,s=4}\n").err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "day 19, line 1, column 9: expected a known workflow, found \"qq\""
+        );
     }
 
     #[test]
+    #[cfg_attr(not(input = "day19"), ignore = "res/day19/input.txt is missing")]
     fn input() {
-        let (part1, part2) = run(Path::new("res/day19/input.txt"));
-        assert_eq!(part1, 432434);
-        assert_eq!(part2, 132557544578569);
+        let (part1, part2) = run(Path::new("res/day19/input.txt")).unwrap();
+        assert_eq!(part1.to_string(), expected(DAY, 1));
+        assert_eq!(part2.to_string(), expected(DAY, 2));
     }
 }
@@ -1,26 +1,203 @@
 use std::{
+    collections::BTreeMap,
     fs::File,
-    io::{self, BufRead, BufReader},
-    str::FromStr,
+    io::{BufReader, Read},
 };
 
-enum Color {
-    Red,
-    Green,
-    Blue,
+use crate::{
+    error::{Error, ParseError, Result},
+    solution::Solution,
+};
+
+const DAY: usize = 2;
+
+/// Colors of the puzzle's bag
+const COLORS: [&str; 3] = ["red", "green", "blue"];
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input = Vec<Game>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        let bag = Bag::new([("red", 12), ("green", 13), ("blue", 14)]);
+        Ok(possible_ids(input, &bag).sum())
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        Ok(input.iter().map(|g| g.min_bag().power_of(COLORS)).sum())
+    }
+}
+
+/// Number of cubes of each color, in one round or in a bag
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Draw(BTreeMap<String, usize>);
+
+/// The cubes in the bag a game is played with
+pub type Bag = Draw;
+
+impl Draw {
+    pub fn new<'a>(counts: impl IntoIterator<Item = (&'a str, usize)>) -> Self {
+        let mut draw = Self::default();
+        for (color, count) in counts {
+            draw.add(color, count);
+        }
+        draw
+    }
+
+    /// Cubes of `color`, 0 if it was not drawn
+    pub fn count(&self, color: &str) -> usize {
+        self.0.get(color).copied().unwrap_or(0)
+    }
+
+    /// Colors and counts, in the order of the color names
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.0.iter().map(|(color, &count)| (color.as_str(), count))
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    fn add(&mut self, color: &str, count: usize) {
+        *self.0.entry(color.to_owned()).or_default() += count;
+    }
+
+    /// Whether this could have been drawn from `bag`
+    pub fn fits_in(&self, bag: &Bag) -> bool {
+        self.iter().all(|(color, count)| count <= bag.count(color))
+    }
+
+    /// Product of the counts of all colors present
+    pub fn power(&self) -> usize {
+        self.0.values().product()
+    }
+
+    /// Product of the counts of `colors`, 0 if one of them is missing
+    pub fn power_of<'a>(&self, colors: impl IntoIterator<Item = &'a str>) -> usize {
+        colors.into_iter().map(|color| self.count(color)).product()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub id: usize,
+    pub rounds: Vec<Draw>,
+}
+
+impl Game {
+    /// Parses a line like `Game 1: 3 blue, 4 red; 1 red, 2 green`
+    ///
+    /// Errors point into `line`, including any whitespace around the game.
+    pub fn parse(line: &str) -> Result<Self, ParseError> {
+        let err = |part: &str, expected: &str| ParseError::at(DAY, line, part, expected);
+        let game = line.trim();
+        let rest = game
+            .strip_prefix("Game ")
+            .ok_or_else(|| err(game, "\"Game \""))?;
+        let (num, rest) = rest.split_once(':').ok_or_else(|| err(rest, "':'"))?;
+        let id = num.parse().map_err(|_| err(num, "a game id"))?;
+
+        let mut rounds = vec![];
+        for round in rest.split(';') {
+            let mut draw = Draw::default();
+            for pair in round.split(',') {
+                let pair = pair.trim();
+                let (count, color) = pair
+                    .split_once(' ')
+                    .ok_or_else(|| err(pair, "a count and a color"))?;
+                let count = count.parse().map_err(|_| err(count, "a number"))?;
+                if color.is_empty() || !color.bytes().all(|b| b.is_ascii_alphabetic()) {
+                    return Err(err(color, "a color"));
+                }
+                draw.add(color, count);
+            }
+            rounds.push(draw);
+        }
+        Ok(Self { id, rounds })
+    }
+
+    /// Whether every round could have been drawn from `bag`
+    pub fn is_possible(&self, bag: &Bag) -> bool {
+        self.rounds.iter().all(|round| round.fits_in(bag))
+    }
+
+    /// The fewest cubes of each color the game could have been played with
+    pub fn min_bag(&self) -> Bag {
+        let mut bag = Bag::default();
+        for (color, count) in self.rounds.iter().flat_map(Draw::iter) {
+            let max = bag.0.entry(color.to_owned()).or_default();
+            *max = (*max).max(count);
+        }
+        bag
+    }
+
+    /// Power of the minimum bag over the colors this game draws
+    pub fn power(&self) -> usize {
+        self.min_bag().power()
+    }
+}
+
+/// Parses one game per non-empty line
+pub fn parse(text: &str) -> Result<Vec<Game>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| Game::parse(line).map_err(|e| Error::from(e.on_line(i + 1))))
+        .collect()
 }
 
-impl FromStr for Color {
-    type Err = ();
+/// Ids of the games that could have been played with `bag`
+pub fn possible_ids<'a>(games: &'a [Game], bag: &'a Bag) -> impl Iterator<Item = usize> + 'a {
+    games
+        .iter()
+        .filter(|game| game.is_possible(bag))
+        .map(|game| game.id)
+}
+
+/// Cubes of one color across the rounds of some games
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColorStats {
+    /// Rounds this color was drawn in
+    pub rounds: usize,
+    /// Games this color was drawn in
+    pub games: usize,
+    /// Cubes drawn over all rounds
+    pub total: usize,
+    /// Most cubes drawn in one round
+    pub max: usize,
+}
+
+impl ColorStats {
+    /// Average cubes per round this color was drawn in
+    pub fn mean(&self) -> f64 {
+        self.total as f64 / self.rounds as f64
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "red" => Ok(Color::Red),
-            "green" => Ok(Color::Green),
-            "blue" => Ok(Color::Blue),
-            _ => Err(()),
+/// Statistics of every color drawn in `games`
+pub fn color_stats(games: &[Game]) -> BTreeMap<String, ColorStats> {
+    let mut stats = BTreeMap::<String, ColorStats>::new();
+    for game in games {
+        for round in &game.rounds {
+            for (color, count) in round.iter() {
+                let s = stats.entry(color.to_owned()).or_default();
+                s.rounds += 1;
+                s.total += count;
+                s.max = s.max.max(count);
+            }
+        }
+        for color in game.min_bag().colors() {
+            stats.get_mut(color).expect("drawn above").games += 1;
         }
     }
+    stats
 }
 
 pub enum Task {
@@ -34,75 +211,39 @@ pub enum Task {
     Task2,
 }
 
-pub fn run(path: &str, task: Task) -> io::Result<usize> {
-    let mut reader = BufReader::new(File::open(path).unwrap());
-    let mut buf = String::new();
-
-    let mut sum = 0;
-
-    while let Ok(len) = reader.read_line(&mut buf) {
-        if len == 0 {
-            break;
-        }
-        let line = buf.trim();
-        let line = line.strip_prefix("Game ").unwrap();
-        let (num, line) = line.split_once(':').unwrap();
-        let id: usize = num.parse().unwrap();
-
-        let mut max_red = 0;
-        let mut max_green = 0;
-        let mut max_blue = 0;
-
-        for part in line.split(';') {
-            let mut red = 0;
-            let mut green = 0;
-            let mut blue = 0;
-
-            for pair in part.split(',') {
-                let (count, color) = pair.trim().split_once(' ').unwrap();
-                let count: usize = count.trim().parse().unwrap();
-                let color: Color = color.trim().parse().unwrap();
-                match color {
-                    Color::Red => red += count,
-                    Color::Green => green += count,
-                    Color::Blue => blue += count,
-                }
-            }
+pub fn run(path: &str, task: Task) -> Result<usize> {
+    sum(BufReader::new(File::open(path)?), task)
+}
 
-            max_red = max_red.max(red);
-            max_green = max_green.max(green);
-            max_blue = max_blue.max(blue);
+pub fn sum(mut reader: impl Read, task: Task) -> Result<usize> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let games = parse(&text)?;
+    Ok(match task {
+        Task::Task1 {
+            has_red,
+            has_green,
+            has_blue,
+        } => {
+            let bag = Bag::new([("red", has_red), ("green", has_green), ("blue", has_blue)]);
+            possible_ids(&games, &bag).sum()
         }
-
-        match task {
-            Task::Task1 {
-                has_red,
-                has_green,
-                has_blue,
-            } => {
-                if max_red <= has_red && max_green <= has_green && max_blue <= has_blue {
-                    sum += id;
-                }
-            }
-            Task::Task2 => {
-                sum += max_red * max_green * max_blue;
-            }
-        }
-
-        buf.clear();
-    }
-
-    Ok(sum)
+        Task::Task2 => games.iter().map(|g| g.min_bag().power_of(COLORS)).sum(),
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Task;
+    use super::{Bag, Day2, Draw, Game, Task, DAY};
+    use crate::answers::expected;
+    use crate::solution::Solution;
+
+    const EXAMPLE: &str = include_str!("../res/day2/example.txt");
 
     #[test]
     fn example() {
-        let id_sum = super::run(
-            "res/day2/example.txt",
+        let id_sum = super::sum(
+            EXAMPLE.as_bytes(),
             Task::Task1 {
                 has_red: 12,
                 has_green: 13,
@@ -114,6 +255,68 @@ mod tests {
     }
 
     #[test]
+    fn invalid_color() {
+        let text = "Game 1: 3 blue, 4 red\nGame 2: 1 blue, 2 purp1e\n";
+        let e = super::sum(text.as_bytes(), Task::Task2).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 2, line 2, column 19: expected a color, found \"purp1e\""
+        );
+
+        // columns count the indentation of the line
+        let text = "Game 1: 3 blue\n  Game 2: 1 blue, 2 purp1e\r\n";
+        let e = super::parse(text).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 2, line 2, column 21: expected a color, found \"purp1e\""
+        );
+        let e = super::parse("\tGame x: 1 blue\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 2, line 1, column 7: expected a game id, found \"x\""
+        );
+    }
+
+    #[test]
+    fn games() {
+        let games = super::parse(EXAMPLE).unwrap();
+        assert_eq!(games.len(), 5);
+        assert_eq!(games[0].id, 1);
+        assert_eq!(games[0].rounds[0], Draw::new([("blue", 3), ("red", 4)]));
+        assert_eq!(
+            games[0].min_bag(),
+            Draw::new([("red", 4), ("green", 2), ("blue", 6)])
+        );
+        assert_eq!(games.iter().map(Game::power).sum::<usize>(), 2286);
+
+        let bag = Bag::new([("red", 12), ("green", 13), ("blue", 14)]);
+        let ids = super::possible_ids(&games, &bag).collect::<Vec<_>>();
+        assert_eq!(ids, [1, 2, 5]);
+        let input = Day2::parse(EXAMPLE).unwrap();
+        assert_eq!(Day2::part1(&input).unwrap(), 8);
+        assert_eq!(Day2::part2(&input).unwrap(), 2286);
+    }
+
+    #[test]
+    fn any_colors() {
+        let text = "Game 7: 2 purple, 1 red; 5 purple\nGame 8: 1 gold, 1 gold\n";
+        let games = super::parse(text).unwrap();
+        assert!(!games[0].is_possible(&Bag::new([("red", 9)])));
+        assert!(games[0].is_possible(&Bag::new([("red", 1), ("purple", 5)])));
+        assert_eq!(games[0].power(), 5);
+        assert_eq!(games[0].min_bag().power_of(super::COLORS), 0);
+        assert_eq!(games[1].rounds[0].count("gold"), 2);
+
+        let stats = super::color_stats(&games);
+        assert_eq!(stats.keys().collect::<Vec<_>>(), ["gold", "purple", "red"]);
+        let purple = stats["purple"];
+        assert_eq!((purple.rounds, purple.games, purple.total), (2, 1, 7));
+        assert_eq!(purple.max, 5);
+        assert_eq!(purple.mean(), 3.5);
+    }
+
+    #[test]
+    #[cfg_attr(not(input = "day2"), ignore = "res/day2/input.txt is missing")]
     fn step1() {
         let id_sum = super::run(
             "res/day2/input.txt",
@@ -124,12 +327,13 @@ mod tests {
             },
         )
         .unwrap();
-        assert_eq!(id_sum, 2563);
+        assert_eq!(id_sum.to_string(), expected(DAY, 1));
     }
 
     #[test]
+    #[cfg_attr(not(input = "day2"), ignore = "res/day2/input.txt is missing")]
     fn step2() {
         let power_set_sum = super::run("res/day2/input.txt", Task::Task2).unwrap();
-        assert_eq!(power_set_sum, 70768);
+        assert_eq!(power_set_sum.to_string(), expected(DAY, 2));
     }
 }
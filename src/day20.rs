@@ -3,6 +3,37 @@ use std::{
     path::Path,
 };
 
+use log::debug;
+use num::integer::gcd;
+
+use crate::{
+    error::{Error, ParseError, Result},
+    graph::{Edge, Graph, Node},
+    solution::Solution,
+};
+
+const DAY: usize = 20;
+
+pub struct Day20;
+
+impl Solution for Day20 {
+    type Input = Circuit;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        Ok(pulse_product(input))
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        presses_until_rx(input)
+    }
+}
+
 fn is_not_empty(line: &&str) -> bool {
     !line.is_empty()
 }
@@ -15,42 +46,75 @@ pub enum Kind {
     Nand,
 }
 
-pub fn run(path: &Path) -> usize {
-    let text = std::fs::read_to_string(path).unwrap();
-    let mut broadcaster = vec![];
+/// The modules of the puzzle input and how they are wired
+pub struct Circuit {
+    broadcaster: Vec<(String, usize)>,
+    logic: LogicMap,
+    logic_ids: BTreeMap<String, usize>,
+    usage: BTreeMap<String, usize>,
+}
+
+impl Circuit {
+    fn push_button<'a>(
+        &'a self,
+        state: &mut u128,
+        on_signal: impl FnMut(&Signal<'a>),
+    ) -> (usize, usize, bool) {
+        push_button(
+            &self.broadcaster,
+            &self.logic,
+            &self.logic_ids,
+            state,
+            &self.usage,
+            on_signal,
+        )
+    }
+}
+
+pub fn run(path: &Path) -> Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(pulse_product(&parse(&text)?))
+}
+
+/// Number of state bits available to the flip-flops and conjunction inputs
+const STATE_BITS: usize = u128::BITS as usize;
+
+pub fn parse(text: &str) -> Result<Circuit> {
+    let mut broadcaster = None;
     let mut usage = BTreeMap::<&str, usize>::new();
-    let logic = text
-        .lines()
-        .filter(is_not_empty)
-        .filter_map(|line| {
-            let (key, value) = line.split_once(" -> ").unwrap();
-            let dest = value
-                .split(", ")
-                .map(|n| {
-                    let e = usage.entry(n).or_default();
-                    (n, std::mem::replace(e, *e + 1))
-                })
-                .collect::<Vec<_>>();
-            if key == "broadcaster" {
-                assert!(broadcaster.is_empty(), "broadcaster already set");
-                broadcaster = dest;
-                None
-            } else if let Some(name) = key.strip_prefix('%') {
-                Some((name, (Kind::FlipFlop, dest)))
-            } else if let Some(name) = key.strip_prefix('&') {
-                Some((name, (Kind::Nand, dest)))
-            } else {
-                panic!()
+    let mut logic = BTreeMap::<&str, (Kind, Vec<(&str, usize)>)>::new();
+    for line in text.lines().filter(is_not_empty) {
+        let (key, value) = line
+            .split_once(" -> ")
+            .ok_or_else(|| ParseError::at(DAY, text, line, "a module and its destinations"))?;
+        let dest = value
+            .split(", ")
+            .map(|n| {
+                let e = usage.entry(n).or_default();
+                (n, std::mem::replace(e, *e + 1))
+            })
+            .collect::<Vec<_>>();
+        if key == "broadcaster" {
+            if broadcaster.replace(dest).is_some() {
+                return Err(ParseError::at(DAY, text, key, "a single broadcaster").into());
             }
-        })
-        .collect::<BTreeMap<_, _>>();
-    eprintln!("logic: {logic:?}");
-    eprintln!("usage: {usage:?}");
+        } else if let Some(name) = key.strip_prefix('%') {
+            logic.insert(name, (Kind::FlipFlop, dest));
+        } else if let Some(name) = key.strip_prefix('&') {
+            logic.insert(name, (Kind::Nand, dest));
+        } else {
+            let expected = "'%', '&' or \"broadcaster\"";
+            return Err(ParseError::at(DAY, text, key, expected).into());
+        }
+    }
+    let broadcaster = broadcaster.unwrap_or_default();
+    debug!("logic: {logic:?}");
+    debug!("usage: {usage:?}");
 
     let mut logic_ids = BTreeMap::<&str, usize>::new();
     let mut i = 0;
     for (name, (kind, _)) in &logic {
-        let l_use = usage.get(name).copied().unwrap();
+        let l_use = usage.get(name).copied().unwrap_or_default();
         match kind {
             Kind::FlipFlop => {
                 logic_ids.insert(name, i);
@@ -72,56 +136,194 @@ pub fn run(path: &Path) -> usize {
             index
         });
     }
-    eprintln!("ids: {logic_ids:?}");
+    debug!("ids: {logic_ids:?}");
+    if i > STATE_BITS {
+        return Err(Error::unsolvable(DAY, "circuit has too many state bits"));
+    }
+
+    let owned = |names: BTreeMap<&str, usize>| {
+        names
+            .into_iter()
+            .map(|(name, n)| (name.to_owned(), n))
+            .collect()
+    };
+    Ok(Circuit {
+        broadcaster: owned_dest(broadcaster),
+        logic: logic
+            .into_iter()
+            .map(|(name, (kind, dest))| (name.to_owned(), (kind, owned_dest(dest))))
+            .collect(),
+        logic_ids: owned(logic_ids),
+        usage: owned(usage),
+    })
+}
 
+fn owned_dest(dest: Vec<(&str, usize)>) -> Vec<(String, usize)> {
+    dest.into_iter()
+        .map(|(name, ofs)| (name.to_owned(), ofs))
+        .collect()
+}
+
+/// The modules shaped by their kind, with the broadcaster and `rx` marked
+pub fn graph(circuit: &Circuit) -> Graph {
+    let edges = |dest: &[(String, usize)]| {
+        dest.iter()
+            .map(|(to, _)| Edge {
+                to: to.to_string(),
+                label: None,
+            })
+            .collect()
+    };
+    let mut nodes = vec![Node {
+        kind: Some("broadcaster"),
+        attrs: vec![
+            ("shape", "house"),
+            ("style", "filled"),
+            ("fillcolor", "palegreen"),
+        ],
+        edges: edges(&circuit.broadcaster),
+        ..Node::new("broadcaster")
+    }];
+    for (name, (kind, dest)) in &circuit.logic {
+        let (kind, shape) = match kind {
+            Kind::FlipFlop => ("flip-flop", "box"),
+            Kind::Nand => ("conjunction", "invtriangle"),
+        };
+        nodes.push(Node {
+            kind: Some(kind),
+            attrs: vec![("shape", shape)],
+            edges: edges(dest),
+            ..Node::new(name)
+        });
+    }
+    let outputs = circuit
+        .usage
+        .keys()
+        .filter(|name| !circuit.logic.contains_key(*name));
+    for name in outputs {
+        nodes.push(if name == "rx" {
+            Node {
+                kind: Some("rx"),
+                attrs: vec![
+                    ("shape", "doublecircle"),
+                    ("style", "filled"),
+                    ("fillcolor", "salmon"),
+                ],
+                ..Node::new(name)
+            }
+        } else {
+            Node {
+                kind: Some("output"),
+                ..Node::new(name)
+            }
+        });
+    }
+    Graph { nodes }
+}
+
+pub fn pulse_product(circuit: &Circuit) -> usize {
     let mut state = 0u128;
-    let mut index = 0usize;
-    //let mut record = BTreeMap::new();
-    //let mut timings = vec![];
     let mut sum = (0, 0);
     let mut rx_index = 0;
-    while index < 1000 {
-        //record.insert(state, index);
-        let (low, high, rx) = push_button(&broadcaster, &logic, &logic_ids, &mut state, &usage);
-        //eprintln!("{index:>4} low={low:<3}, high={high:<3} 0x{state:032x}");
-        //timings.push((low, high));
-        if index < 1000 {
-            sum.0 += low;
-            sum.1 += high;
-        }
-        index += 1;
-
+    for index in 1..=1000 {
+        let (low, high, rx) = circuit.push_button(&mut state, |_| {});
+        sum.0 += low;
+        sum.1 += high;
         if rx && rx_index == 0 {
             rx_index = index;
         }
     }
-    /*if let Some(repeat_index) = record.get(&state) {
-        eprintln!("repeat to {repeat_index} at {index}");
-    } else {
-        eprintln!("Clicked the button {index} times");
-    }*/
-    eprintln!("{sum:?}");
-    eprintln!("{rx_index}");
-    //eprintln!("{:?}", timings);
-    //eprintln!("{:?}", record);
+    debug!("low and high pulses: {sum:?}");
+    debug!("first press reaching rx: {rx_index}");
     sum.0 * sum.1
 }
 
-pub type LogicMap<'a> = BTreeMap<&'a str, (Kind, Vec<(&'a str, usize)>)>;
+/// Upper bound for the button presses in [`presses_until_rx`]
+const MAX_PRESSES: usize = 1 << 20;
 
-fn push_button(
-    broadcaster: &[(&str, usize)],
-    logic: &LogicMap,
-    logic_ids: &BTreeMap<&str, usize>,
+/// Part 2
+///
+/// Pressing the button until `rx` gets a low pulse takes far too long, so this
+/// relies on how the puzzle inputs are built: `rx` is fed by a single
+/// conjunction, and each of its inputs is a counter that sends it a high pulse
+/// every `n` presses, starting with press `n`. Then `rx` first gets a low pulse
+/// after the least common multiple of the periods. Fails with a reason if the
+/// circuit is built differently, or if a period exceeds [`MAX_PRESSES`] / 2.
+pub fn presses_until_rx(circuit: &Circuit) -> Result<usize> {
+    let unsolvable = |reason: String| Error::unsolvable(DAY, reason);
+    let mut feeders = circuit
+        .logic
+        .iter()
+        .filter(|(_, (_, dest))| dest.iter().any(|(target, _)| target == "rx"));
+    let (feeder, (kind, _)) = match (feeders.next(), feeders.next()) {
+        (Some(feeder), None) => feeder,
+        (None, _) => return Err(unsolvable("no module sends pulses to rx".into())),
+        (Some(_), Some(_)) => {
+            return Err(unsolvable("more than one module sends pulses to rx".into()))
+        }
+    };
+    if *kind != Kind::Nand {
+        return Err(unsolvable(format!(
+            "rx is fed by {feeder}, which is not a conjunction"
+        )));
+    }
+    let inputs = circuit.usage.get(feeder).copied().unwrap_or_default();
+
+    // the first two presses on which each input sends a high pulse to the feeder
+    let mut highs = BTreeMap::<&str, Vec<usize>>::new();
+    let mut state = 0u128;
+    for press in 1..=MAX_PRESSES {
+        circuit.push_button(&mut state, |signal| {
+            if signal.target == feeder && signal.kind == SignalKind::High {
+                let presses = highs.entry(signal.src).or_default();
+                if presses.len() < 2 && presses.last() != Some(&press) {
+                    presses.push(press);
+                }
+            }
+        });
+        if highs.len() == inputs && highs.values().all(|presses| presses.len() == 2) {
+            break;
+        }
+    }
+    debug!("high pulses into {feeder}: {highs:?}");
+    if highs.len() < inputs || highs.values().any(|presses| presses.len() < 2) {
+        let reason = format!(
+            "the inputs of {feeder} do not all send high pulses twice in {MAX_PRESSES} presses"
+        );
+        return Err(unsolvable(reason));
+    }
+    let mut presses = 1usize;
+    for (input, cycle) in highs {
+        if cycle[1] != 2 * cycle[0] {
+            let reason = format!(
+                "{input} sends high pulses to {feeder} on presses {} and {}, not on a fixed cycle",
+                cycle[0], cycle[1]
+            );
+            return Err(unsolvable(reason));
+        }
+        presses = (presses / gcd(presses, cycle[0]))
+            .checked_mul(cycle[0])
+            .ok_or_else(|| unsolvable("the answer does not fit in usize".into()))?;
+    }
+    Ok(presses)
+}
+
+pub type LogicMap = BTreeMap<String, (Kind, Vec<(String, usize)>)>;
+
+fn push_button<'a>(
+    broadcaster: &'a [(String, usize)],
+    logic: &'a LogicMap,
+    logic_ids: &BTreeMap<String, usize>,
     state: &mut u128,
-    usage: &BTreeMap<&str, usize>,
+    usage: &BTreeMap<String, usize>,
+    mut on_signal: impl FnMut(&Signal<'a>),
 ) -> (usize, usize, bool) {
     let mut queue = VecDeque::<Signal>::new();
     // Start out with all broadcast signals in the queue
-    for signal in broadcaster.iter().copied().map(|(target, ofs)| Signal {
+    for signal in broadcaster.iter().map(|(target, ofs)| Signal {
         src: "broadcaster",
         target,
-        ofs,
+        ofs: *ofs,
         kind: SignalKind::Low,
     }) {
         queue.push_back(signal);
@@ -130,13 +332,12 @@ fn push_button(
     let mut low_count = 1; // button -> broadcaster
     let mut high_count = 0;
     let mut rx = false;
-    //eprintln!("<-- click :D -->");
     while let Some(next) = queue.pop_front() {
+        on_signal(&next);
         match next.kind {
             SignalKind::Low => low_count += 1,
             SignalKind::High => high_count += 1,
         }
-        //eprintln!("next: {next:?} 0x{state:032x}");
         let src = next.target;
         if let Some((mod_kind, dest)) = logic.get(src) {
             match (*mod_kind, next.kind) {
@@ -149,12 +350,12 @@ fn push_button(
                     } else {
                         SignalKind::Low
                     };
-                    for (target, ofs) in dest.iter().copied() {
+                    for (target, ofs) in dest {
                         queue.push_back(Signal {
                             kind,
                             src,
                             target,
-                            ofs,
+                            ofs: *ofs,
                         });
                     }
                 }
@@ -182,12 +383,12 @@ fn push_button(
                     };
 
                     // all bits high
-                    for (target, ofs) in dest.iter().copied() {
+                    for (target, ofs) in dest {
                         let signal = Signal {
                             kind,
                             src,
                             target,
-                            ofs,
+                            ofs: *ofs,
                         };
                         queue.push_back(signal);
                     }
@@ -219,25 +420,104 @@ pub struct Signal<'a> {
 mod tests {
     use std::path::Path;
 
-    use super::run;
+    use super::{parse, presses_until_rx, pulse_product, run, DAY};
+    use crate::answers::expected;
+
+    const EXAMPLE1: &str = include_str!("../res/day20/example1.txt");
+    const EXAMPLE2: &str = include_str!("../res/day20/example2.txt");
 
     #[test]
     fn example1() {
-        assert_eq!(run(Path::new("res/day20/example1.txt")), 32000000);
+        assert_eq!(pulse_product(&parse(EXAMPLE1).unwrap()), 32000000);
     }
 
     #[test]
     fn example2() {
-        assert_eq!(run(Path::new("res/day20/example2.txt")), 11687500);
+        assert_eq!(pulse_product(&parse(EXAMPLE2).unwrap()), 11687500);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day20"), ignore = "res/day20/input.txt is missing")]
     fn part1() {
-        assert_eq!(run(Path::new("res/day20/input.txt")), 730797576);
+        let product = run(Path::new("res/day20/input.txt")).unwrap();
+        assert_eq!(product.to_string(), expected(DAY, 1));
+    }
+
+    #[test]
+    fn invalid_module() {
+        let e = parse("broadcaster -> a\n%a -> b\n*b -> a\n").err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "day 20, line 3, column 1: expected '%', '&' or \"broadcaster\", found \"*b\""
+        );
+    }
+
+    /// Two counters with periods 5 and 3 feeding `rx`, built like the puzzle input
+    const COUNTERS: &str = "broadcaster -> a0, b0
+%a0 -> a1, ca
+%a1 -> a2
+%a2 -> ca
+&ca -> a0, a1, ia
+&ia -> fd
+%b0 -> b1, cb
+%b1 -> cb
+&cb -> b0, ib
+&ib -> fd
+&fd -> rx
+";
+
+    #[test]
+    fn graph() {
+        let graph = super::graph(&parse(COUNTERS).unwrap());
+        let kinds = graph.nodes.iter().map(|n| (n.id.as_str(), n.kind.unwrap()));
+        let kinds = kinds.collect::<Vec<_>>();
+        assert_eq!(kinds[0], ("broadcaster", "broadcaster"));
+        assert!(kinds.contains(&("a0", "flip-flop")) && kinds.contains(&("fd", "conjunction")));
+        assert_eq!(kinds.last(), Some(&("rx", "rx")));
+        let dot = graph.to_dot("day20");
+        assert!(dot.contains("    \"ca\" [shape=\"invtriangle\"];\n"));
+        assert!(dot.contains("    \"fd\" -> \"rx\";\n"));
+    }
+
+    #[test]
+    fn example_rx() {
+        assert_eq!(presses_until_rx(&parse(COUNTERS).unwrap()).unwrap(), 15);
+    }
+
+    #[test]
+    fn rx_unsolvable() {
+        let error = |text| {
+            presses_until_rx(&parse(text).unwrap())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("broadcaster -> a\n%a -> b\n"),
+            "day 20: no module sends pulses to rx"
+        );
+        assert_eq!(
+            error("broadcaster -> a, b\n%a -> rx\n%b -> rx\n"),
+            "day 20: more than one module sends pulses to rx"
+        );
+        assert_eq!(
+            error("broadcaster -> a\n%a -> rx\n"),
+            "day 20: rx is fed by a, which is not a conjunction"
+        );
+        assert_eq!(
+            error("broadcaster -> a\n%a -> c\n&c -> rx\n"),
+            "day 20: a sends high pulses to c on presses 1 and 3, not on a fixed cycle"
+        );
+        // `x` never gets a pulse to send on
+        assert_eq!(
+            error("broadcaster -> a\n%a -> b\n%b -> c\n%x -> c\n&c -> rx\n"),
+            "day 20: the inputs of c do not all send high pulses twice in 1048576 presses"
+        );
     }
 
     #[test]
+    #[cfg_attr(not(input = "day20"), ignore = "res/day20/input.txt is missing")]
     fn part2() {
-        run(Path::new("res/day20/input.txt"));
+        let text = std::fs::read_to_string("res/day20/input.txt").unwrap();
+        assert!(presses_until_rx(&parse(&text).unwrap()).is_ok());
     }
 }
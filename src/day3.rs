@@ -1,138 +1,165 @@
-use std::collections::{btree_map::Entry, BTreeMap};
+use std::collections::{BTreeMap, BTreeSet};
 
-pub enum GearState {
-    One(usize),
-    Two(usize),
-    ThreeOrMore,
-}
+use crate::{
+    error::{ParseError, Result},
+    solution::Solution,
+    util::{Grid, Pos},
+};
 
-pub enum Task {
-    Part1,
-    Part2 {
-        maybe_gears: BTreeMap<usize, GearState>,
-    },
-}
+const DAY: usize = 3;
 
-pub fn read_schematic(path: &str, mut task: Task) -> usize {
-    let schematic = std::fs::read_to_string(path).unwrap();
-    let stride = schematic.split_once('\n').unwrap().0.len() + 1;
-    let height = schematic.len() / stride;
+pub struct Day3;
 
-    assert_eq!(stride * height, schematic.len());
+impl Solution for Day3 {
+    type Input = Schematic;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-    let numbers = regex::Regex::new("[0-9]+").unwrap();
+    fn parse(text: &str) -> Result<Self::Input> {
+        Schematic::parse(text)
+    }
 
-    let mut sum = 0;
-    for (y, line) in schematic.lines().enumerate() {
-        if line.is_empty() {
-            break;
-        }
+    fn part1(input: &Self::Input) -> Result<usize> {
+        Ok(input.parts().map(|n| n.value).sum())
+    }
 
-        for _match in numbers.find_iter(line) {
-            let x = _match.start();
-            let text = _match.as_str();
-            let len = text.len();
-            let value: usize = text.parse().unwrap();
-            let mut is_part = false;
-            let mut gear_pos = vec![];
-
-            if y > 0 {
-                // Check above
-                if x > 0 {
-                    // top-left
-                    let pos = (y - 1) * stride + (x - 1);
-                    let c = schematic.as_bytes()[pos];
-                    is_part |= is_symbol(c as char);
-                    if is_gear(c) {
-                        gear_pos.push(pos);
-                    }
-                }
-                for i in 0..=len {
-                    let pos = (y - 1) * stride + (x + i);
-                    let c = schematic.as_bytes()[pos];
-                    is_part |= is_symbol(c as char);
-                    if is_gear(c) {
-                        gear_pos.push(pos);
-                    }
-                }
-            }
+    fn part2(input: &Self::Input) -> Result<usize> {
+        Ok(input.gear_ratios().sum())
+    }
+}
 
-            // Check left
-            if x > 0 {
-                // left
-                let pos = y * stride + (x - 1);
-                let c = schematic.as_bytes()[pos];
-                is_part |= is_symbol(c as char);
-                if is_gear(c) {
-                    gear_pos.push(pos);
-                }
-            }
+/// A number in the schematic, with the symbols next to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Number {
+    pub value: usize,
+    /// Position of the first digit
+    pub pos: Pos<usize>,
+    pub len: usize,
+    /// Indices into [`Schematic::symbols`]
+    pub symbols: Vec<usize>,
+}
 
-            // Check right
-            let pos = y * stride + (x + len);
-            let c = schematic.as_bytes()[pos];
-            is_part |= is_symbol(c as char);
-            if is_gear(c) {
-                gear_pos.push(pos);
-            }
+/// A symbol in the schematic, with the numbers next to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub kind: char,
+    pub pos: Pos<usize>,
+    /// Indices into [`Schematic::numbers`]
+    pub numbers: Vec<usize>,
+}
 
-            if y + 1 < height {
-                // Check below
-                if x > 0 {
-                    // bottom-left
-                    let pos = (y + 1) * stride + (x - 1);
-                    let c = schematic.as_bytes()[pos];
-                    is_part |= is_symbol(c as char);
-                    if is_gear(c) {
-                        gear_pos.push(pos);
-                    }
-                }
-                for i in 0..=len {
-                    let pos = (y + 1) * stride + (x + i);
-                    let c = schematic.as_bytes()[pos];
-                    is_part |= is_symbol(c as char);
-                    if is_gear(c) {
-                        gear_pos.push(pos);
-                    }
+/// Numbers and symbols of an engine schematic and which of them are adjacent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schematic {
+    /// In reading order
+    pub numbers: Vec<Number>,
+    /// In reading order
+    pub symbols: Vec<Symbol>,
+}
+
+impl Schematic {
+    pub fn parse(schematic: &str) -> Result<Self> {
+        let grid = Grid::parse(DAY, schematic, "an ASCII character", |c| Some(c as u8))?;
+
+        let mut symbols = vec![];
+        let mut symbol_at = BTreeMap::new();
+        for (y, row) in grid.rows().enumerate() {
+            for (x, &c) in row.iter().enumerate() {
+                if is_symbol(c as char) {
+                    symbol_at.insert((x, y), symbols.len());
+                    symbols.push(Symbol {
+                        kind: c as char,
+                        pos: (x, y),
+                        numbers: vec![],
+                    });
                 }
             }
+        }
 
-            if is_part {
-                match &mut task {
-                    Task::Part1 => {
-                        sum += value; // part id
-                    }
-                    Task::Part2 { maybe_gears } => {
-                        for pos in gear_pos {
-                            match maybe_gears.entry(pos) {
-                                Entry::Vacant(v) => {
-                                    v.insert(GearState::One(value));
-                                }
-                                Entry::Occupied(mut o) => {
-                                    o.insert(match o.get() {
-                                        GearState::One(value1) => GearState::Two(value * value1),
-                                        GearState::Two(_) | GearState::ThreeOrMore => {
-                                            GearState::ThreeOrMore
-                                        }
-                                    });
-                                }
-                            }
-                        }
-                    }
+        let digits = regex::Regex::new("[0-9]+").unwrap();
+        let mut numbers = vec![];
+        for (y, line) in schematic.lines().enumerate() {
+            for found in digits.find_iter(line) {
+                let span = found.range();
+                let text = found.as_str();
+                let value = text
+                    .parse()
+                    .map_err(|_| ParseError::at(DAY, schematic, text, "a part number"))?;
+
+                let adjacent = span
+                    .clone()
+                    .flat_map(|x| grid.neighbors8((x, y)))
+                    .filter_map(|pos| symbol_at.get(&pos).copied())
+                    .collect::<BTreeSet<usize>>();
+                for &symbol in &adjacent {
+                    symbols[symbol].numbers.push(numbers.len());
                 }
+                numbers.push(Number {
+                    value,
+                    pos: (span.start, y),
+                    len: span.len(),
+                    symbols: adjacent.into_iter().collect(),
+                });
             }
         }
+        Ok(Self { numbers, symbols })
+    }
+
+    /// Numbers next to at least one symbol
+    pub fn parts(&self) -> impl Iterator<Item = &Number> {
+        self.numbers.iter().filter(|n| !n.symbols.is_empty())
+    }
+
+    /// Numbers next to at least one symbol of `kind`
+    pub fn parts_of(&self, kind: char) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .filter(move |n| n.symbols.iter().any(|&s| self.symbols[s].kind == kind))
+    }
+
+    /// Numbers next to `symbol`
+    pub fn adjacent<'a>(&'a self, symbol: &'a Symbol) -> impl Iterator<Item = &'a Number> {
+        symbol.numbers.iter().map(|&n| &self.numbers[n])
     }
+
+    /// Symbols of `kind` next to exactly `count` numbers
+    pub fn symbols_with(&self, kind: char, count: usize) -> impl Iterator<Item = &Symbol> {
+        self.symbols
+            .iter()
+            .filter(move |s| s.kind == kind && s.numbers.len() == count)
+    }
+
+    /// Product of the numbers next to `symbol`
+    pub fn product(&self, symbol: &Symbol) -> usize {
+        self.adjacent(symbol).map(|n| n.value).product()
+    }
+
+    /// Sum of the numbers next to `symbol`
+    pub fn sum(&self, symbol: &Symbol) -> usize {
+        self.adjacent(symbol).map(|n| n.value).sum()
+    }
+
+    /// Ratios of the `*` next to exactly two numbers
+    pub fn gear_ratios(&self) -> impl Iterator<Item = usize> + '_ {
+        self.symbols_with('*', 2).map(|s| self.product(s))
+    }
+}
+
+pub enum Task {
+    Part1,
+    Part2,
+}
+
+pub fn read_schematic(path: &str, task: Task) -> Result<usize> {
+    let schematic = std::fs::read_to_string(path)?;
+    schematic_sum(&schematic, task)
+}
+
+pub fn schematic_sum(schematic: &str, task: Task) -> Result<usize> {
+    let schematic = Schematic::parse(schematic)?;
     match task {
-        Task::Part1 => sum,
-        Task::Part2 { maybe_gears } => maybe_gears
-            .into_iter()
-            .filter_map(|(_, f)| match f {
-                GearState::One(_) => None,
-                GearState::Two(ratio) => Some(ratio),
-                GearState::ThreeOrMore => None,
-            })
-            .sum(),
+        Task::Part1 => Day3::part1(&schematic),
+        Task::Part2 => Day3::part2(&schematic),
     }
 }
 
@@ -140,47 +167,68 @@ fn is_symbol(c: char) -> bool {
     !matches!(c, '.' | '\r' | '\n' | '0'..='9')
 }
 
-fn is_gear(c: u8) -> bool {
-    c == b'*'
-}
-
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap;
+    use super::{Schematic, Task, DAY};
+    use crate::answers::expected;
 
-    use crate::day3::Task;
+    const EXAMPLE: &str = include_str!("../res/day3/example.txt");
+    const EXAMPLE2: &str = include_str!("../res/day3/example2.txt");
 
     #[test]
     fn example() {
-        let sum = super::read_schematic("res/day3/example.txt", Task::Part1);
+        let sum = super::schematic_sum(EXAMPLE, Task::Part1).unwrap();
         assert_eq!(sum, 4361);
     }
 
     #[test]
+    fn ragged() {
+        let e = super::schematic_sum("467..\n...*\n", Task::Part1).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 3, line 2, column 1: expected a line of width 5, found \"...*\""
+        );
+    }
+
+    #[test]
+    fn graph() {
+        let schematic = Schematic::parse(EXAMPLE).unwrap();
+        assert_eq!(schematic.numbers.len(), 10);
+        assert_eq!(schematic.symbols.len(), 6);
+        let star = &schematic.symbols[0];
+        assert_eq!((star.kind, star.pos), ('*', (3, 1)));
+        let near = schematic
+            .adjacent(star)
+            .map(|n| n.value)
+            .collect::<Vec<_>>();
+        assert_eq!(near, [467, 35]);
+        assert_eq!(schematic.sum(star), 502);
+
+        let lonely = schematic.numbers.iter().filter(|n| n.symbols.is_empty());
+        assert_eq!(lonely.map(|n| n.value).collect::<Vec<_>>(), [114, 58]);
+        let dollars = schematic.parts_of('$').map(|n| n.value).collect::<Vec<_>>();
+        assert_eq!(dollars, [664]);
+        assert_eq!(schematic.symbols_with('*', 1).count(), 1);
+        assert_eq!(schematic.gear_ratios().collect::<Vec<_>>(), [16345, 451490]);
+    }
+
+    #[test]
+    #[cfg_attr(not(input = "day3"), ignore = "res/day3/input.txt is missing")]
     fn part1() {
-        let sum = super::read_schematic("res/day3/input.txt", Task::Part1);
-        assert_eq!(sum, 540025);
+        let sum = super::read_schematic("res/day3/input.txt", Task::Part1).unwrap();
+        assert_eq!(sum.to_string(), expected(DAY, 1));
     }
 
     #[test]
     fn example2() {
-        let sum = super::read_schematic(
-            "res/day3/example2.txt",
-            Task::Part2 {
-                maybe_gears: BTreeMap::new(),
-            },
-        );
+        let sum = super::schematic_sum(EXAMPLE2, Task::Part2).unwrap();
         assert_eq!(sum, 467835);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day3"), ignore = "res/day3/input.txt is missing")]
     fn part2() {
-        let sum = super::read_schematic(
-            "res/day3/input.txt",
-            Task::Part2 {
-                maybe_gears: BTreeMap::new(),
-            },
-        );
-        assert_eq!(sum, 84584891);
+        let sum = super::read_schematic("res/day3/input.txt", Task::Part2).unwrap();
+        assert_eq!(sum.to_string(), expected(DAY, 2));
     }
 }
@@ -1,16 +1,40 @@
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeSet, VecDeque},
     fs::File,
     io::{BufRead, BufReader},
     str::FromStr,
 };
 
+use crate::{
+    error::{Error, ParseError, Result},
+    solution::Solution,
+};
+
+const DAY: usize = 4;
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    type Input = Vec<Card>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        points(input, doubling)
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        Ok(copies(input, PUZZLE_RULE)?.iter().sum())
+    }
+}
+
 pub enum Part {
     Part1,
-    Part2 {
-        /// Map from ID to count
-        cards: BTreeMap<usize, usize>,
-    },
+    Part2,
 }
 
 pub struct Card {
@@ -20,41 +44,138 @@ pub struct Card {
 }
 
 impl Card {
-    fn check(&self) -> usize {
-        let mut points = 0;
-        for num in &self.you_have {
-            if self.winning.contains(num) {
-                if points == 0 {
-                    points = 1;
-                } else {
-                    points <<= 1;
-                }
-            }
+    /// Numbers you have that are winning
+    pub fn matches(&self) -> usize {
+        self.you_have
+            .iter()
+            .filter(|num| self.winning.contains(num))
+            .count()
+    }
+}
+
+/// One point for the first match, doubled for every further one
+///
+/// Fails if the points do not fit in `usize`.
+pub fn doubling(matches: usize) -> Result<usize> {
+    match matches {
+        0 => Ok(0),
+        n => u32::try_from(n - 1)
+            .ok()
+            .and_then(|shift| 1usize.checked_shl(shift))
+            .ok_or_else(too_many_points),
+    }
+}
+
+/// One point per match
+pub fn linear(matches: usize) -> Result<usize> {
+    Ok(matches)
+}
+
+fn too_many_points() -> Error {
+    Error::unsolvable(DAY, "the points do not fit in usize")
+}
+
+/// Sum of the points of `cards` under `score`, e.g. [`doubling`]
+pub fn points(cards: &[Card], score: impl Fn(usize) -> Result<usize>) -> Result<usize> {
+    cards.iter().try_fold(0usize, |sum, card| {
+        sum.checked_add(score(card.matches())?)
+            .ok_or_else(too_many_points)
+    })
+}
+
+/// Which cards a card with `k` matches wins copies of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyRule {
+    /// The next `k` cards, which must all exist
+    Next,
+    /// The next `k` cards, as far as the table goes
+    Capped,
+}
+
+/// The rule for the puzzle answer, wherever it is computed
+const PUZZLE_RULE: CopyRule = CopyRule::Capped;
+
+/// Copies won by a stream of cards, only keeping the copies of cards not yet seen
+pub struct Cascade {
+    rule: CopyRule,
+    /// Copies won of the cards following the last one
+    pending: VecDeque<usize>,
+    total: usize,
+}
+
+impl Cascade {
+    pub fn new(rule: CopyRule) -> Self {
+        Self {
+            rule,
+            pending: VecDeque::new(),
+            total: 0,
         }
-        points
     }
 
-    fn check_part2(&self) -> usize {
-        let mut wins = 0;
-        for num in &self.you_have {
-            if self.winning.contains(num) {
-                wins += 1;
+    /// Adds the next card and returns how many of it you end up with
+    pub fn push(&mut self, matches: usize) -> usize {
+        let count = 1 + self.pending.pop_front().unwrap_or(0);
+        for next in 0..matches {
+            match self.pending.get_mut(next) {
+                Some(copies) => *copies += count,
+                None => self.pending.push_back(count),
             }
         }
-        wins
+        self.total += count;
+        count
+    }
+
+    /// Number of cards you end up with
+    ///
+    /// Fails under [`CopyRule::Next`] if copies were won past the last card.
+    pub fn finish(self) -> Result<usize> {
+        if self.rule == CopyRule::Next && !self.pending.is_empty() {
+            let reason = "copies were won of cards past the end of the table";
+            return Err(Error::unsolvable(DAY, reason));
+        }
+        Ok(self.total)
     }
 }
 
+/// How many of each card you end up with
+pub fn copies(cards: &[Card], rule: CopyRule) -> Result<Vec<usize>> {
+    let mut cascade = Cascade::new(rule);
+    let counts = cards
+        .iter()
+        .map(|card| cascade.push(card.matches()))
+        .collect();
+    cascade.finish()?;
+    Ok(counts)
+}
+
+/// Parses one card per non-empty line
+pub fn parse(text: &str) -> Result<Vec<Card>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            Ok(line
+                .trim()
+                .parse()
+                .map_err(|e: ParseError| e.on_line(i + 1))?)
+        })
+        .collect()
+}
+
 impl FromStr for Card {
-    type Err = ();
-
-    fn from_str(line: &str) -> Result<Self, Self::Err> {
-        let line = line.strip_prefix("Card ").unwrap();
-        let (id_str, line) = line.split_once(':').unwrap();
-        let id: usize = id_str.trim().parse().unwrap();
-        let (winning, you_have) = line.split_once('|').unwrap();
-        let winning = parse_numbers(winning).collect::<BTreeSet<usize>>();
-        let you_have = parse_numbers(you_have).collect::<Vec<usize>>();
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let err = |part, expected| ParseError::at(DAY, text, part, expected);
+        let line = text
+            .strip_prefix("Card ")
+            .ok_or_else(|| err(text, "\"Card \""))?;
+        let (id_str, line) = line.split_once(':').ok_or_else(|| err(line, "':'"))?;
+        let id_str = id_str.trim();
+        let id: usize = id_str.parse().map_err(|_| err(id_str, "a card id"))?;
+        let (winning, you_have) = line.split_once('|').ok_or_else(|| err(line, "'|'"))?;
+        let winning = parse_numbers(text, winning)?;
+        let you_have = parse_numbers(text, you_have)?;
         Ok(Self {
             id,
             winning,
@@ -63,85 +184,131 @@ impl FromStr for Card {
     }
 }
 
-fn parse_numbers(winning: &str) -> impl Iterator<Item = usize> + '_ {
-    winning
+fn parse_numbers<C: FromIterator<usize>>(line: &str, numbers: &str) -> Result<C, ParseError> {
+    numbers
         .split(' ')
         .filter(|x| !x.is_empty())
-        .map(|x| x.parse().unwrap())
+        .map(|x| {
+            x.parse()
+                .map_err(|_| ParseError::at(DAY, line, x, "a number"))
+        })
+        .collect()
 }
 
-pub fn run(path: &str, mut part: Part) -> usize {
-    let file = File::open(path).unwrap();
-    let mut reader = BufReader::new(file);
+pub fn run(path: &str, part: Part) -> Result<usize> {
+    let file = File::open(path)?;
+    sum(BufReader::new(file), part)
+}
 
-    let mut sum = 0;
-    let mut debug = vec![];
+pub fn sum(mut reader: impl BufRead, part: Part) -> Result<usize> {
+    let mut points = 0usize;
+    let mut cascade = Cascade::new(PUZZLE_RULE);
     let mut buf = String::new();
-    while let Ok(len) = reader.read_line(&mut buf) {
-        if len == 0 {
-            break;
+    let mut line_no = 0;
+    while reader.read_line(&mut buf)? > 0 {
+        line_no += 1;
+        let line = buf.trim();
+        if line.is_empty() {
+            buf.clear();
+            continue;
         }
 
-        let card: Card = buf.trim().parse().unwrap();
-        match &mut part {
+        let card: Card = line.parse().map_err(|e: ParseError| e.on_line(line_no))?;
+        match part {
             Part::Part1 => {
-                sum += card.check();
-            }
-            Part::Part2 { cards } => {
-                let copies = cards.get(&card.id).copied().unwrap_or(0) + 1;
-                debug.push(copies);
-                sum += copies;
-                let to_get = card.check_part2();
-                for offset in 1..=to_get {
-                    *cards.entry(card.id + offset).or_default() += copies;
-                }
+                points = points
+                    .checked_add(doubling(card.matches())?)
+                    .ok_or_else(too_many_points)?
             }
+            Part::Part2 => _ = cascade.push(card.matches()),
         }
         buf.clear();
     }
-    /*if let Part::Part2 { cards } = &mut part {
-        panic!("{debug:?} {cards:?} {sum:?}");
-    }*/
-    sum
+    match part {
+        Part::Part1 => Ok(points),
+        Part::Part2 => cascade.finish(),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap;
+    use super::{CopyRule, Day4, Part, DAY};
+    use crate::answers::expected;
+    use crate::error::Result;
+    use crate::solution::Solution;
 
-    use super::Part;
+    const EXAMPLE: &str = include_str!("../res/day4/example.txt");
+    const EXAMPLE2: &str = include_str!("../res/day4/example2.txt");
 
     #[test]
     fn example() {
-        let sum = super::run("res/day4/example.txt", Part::Part1);
+        let sum = super::sum(EXAMPLE.as_bytes(), Part::Part1).unwrap();
         assert_eq!(sum, 13);
     }
 
     #[test]
+    fn invalid_number() {
+        let text = "Card 1: 41 48 | 83 86\nCard 2: 13 x2 | 61 30\n";
+        let e = super::sum(text.as_bytes(), Part::Part1).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 4, line 2, column 12: expected a number, found \"x2\""
+        );
+    }
+
+    #[test]
+    #[cfg_attr(not(input = "day4"), ignore = "res/day4/input.txt is missing")]
     fn step1() {
-        let sum = super::run("res/day4/input.txt", Part::Part1);
-        assert_eq!(sum, 24706);
+        let sum = super::run("res/day4/input.txt", Part::Part1).unwrap();
+        assert_eq!(sum.to_string(), expected(DAY, 1));
     }
 
     #[test]
     fn example2() {
-        let sum = super::run(
-            "res/day4/example2.txt",
-            Part::Part2 {
-                cards: BTreeMap::new(),
-            },
-        );
+        let sum = super::sum(EXAMPLE2.as_bytes(), Part::Part2).unwrap();
         assert_eq!(sum, 30);
     }
 
     #[test]
-    fn step2() {
-        let sum = super::run(
-            "res/day4/input.txt",
-            Part::Part2 {
-                cards: BTreeMap::new(),
-            },
+    fn cascade() {
+        let cards = super::parse(EXAMPLE2).unwrap();
+        let copies = super::copies(&cards, CopyRule::Next).unwrap();
+        assert_eq!(copies, [1, 2, 4, 8, 14, 1]);
+        let points = |score: fn(usize) -> Result<usize>| super::points(&cards, score).unwrap();
+        assert_eq!(points(super::linear), 4 + 2 + 2 + 1);
+        assert_eq!(points(|k| Ok(k * k)), 16 + 4 + 4 + 1);
+        assert_eq!(Day4::part2(&cards).unwrap(), 30);
+
+        // the last card wins copies of a card that is not on the table
+        let capped = "Card 1: 1 2 | 1 3\nCard 2: 4 5 | 4 5\n";
+        let cards = super::parse(capped).unwrap();
+        assert_eq!(super::copies(&cards, CopyRule::Capped).unwrap(), [1, 2]);
+        assert!(super::copies(&cards, CopyRule::Next).is_err());
+        assert_eq!(super::sum(capped.as_bytes(), Part::Part2).unwrap(), 3);
+        assert_eq!(Day4::part2(&cards).unwrap(), 3);
+    }
+
+    #[test]
+    fn too_many_points() {
+        assert_eq!(super::doubling(64).unwrap(), 1 << 63);
+        assert_eq!(
+            super::doubling(65).unwrap_err().to_string(),
+            "day 4: the points do not fit in usize"
         );
-        assert_eq!(sum, 13114317);
+        let numbers = (1..=64)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = format!("Card 1: {numbers} | {numbers}\nCard 2: {numbers} | {numbers}\n");
+        let cards = super::parse(&text).unwrap();
+        assert!(Day4::part1(&cards).is_err());
+        assert!(super::sum(text.as_bytes(), Part::Part1).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(not(input = "day4"), ignore = "res/day4/input.txt is missing")]
+    fn step2() {
+        let sum = super::run("res/day4/input.txt", Part::Part2).unwrap();
+        assert_eq!(sum.to_string(), expected(DAY, 2));
     }
 }
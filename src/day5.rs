@@ -1,20 +1,211 @@
-use std::{cmp::Ordering, ops::Range, str::FromStr};
+use std::{borrow::Cow, cmp::Ordering, ops::Range, str::FromStr};
 
 use nom::{
     bytes::complete::tag,
     character::complete::{alpha1, digit1},
-    combinator::{map, map_res},
+    combinator::{consumed, map, map_res},
+    error::{context, VerboseError, VerboseErrorKind},
     multi::{many1, separated_list0},
     sequence::{pair, preceded, terminated, tuple},
     IResult,
 };
 
+use crate::{
+    error::{Error, ParseError, Result},
+    solution::{Solution, Variant},
+};
+
+const DAY: usize = 5;
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    type Input = Input;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        input.lowest_location()
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        input.lowest_location_composed()
+    }
+
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![
+            ("part2 (range lists)", |input| {
+                Ok(input.lowest_location_part2_alt()?.to_string())
+            }),
+            ("part2 (every seed)", |input| {
+                Ok(input.lowest_location_part2()?.to_string())
+            }),
+        ]
+    }
+}
+
+/// Sorted, disjoint and non-adjacent ranges of numbers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<Range<usize>>,
+}
+
+impl IntervalSet {
+    pub fn new(ranges: impl IntoIterator<Item = Range<usize>>) -> Self {
+        let mut sorted = ranges
+            .into_iter()
+            .filter(|r| !r.is_empty())
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|r| r.start);
+        let mut ranges: Vec<Range<usize>> = vec![];
+        for range in sorted {
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => ranges.push(range),
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of numbers in the set
+    pub fn len(&self) -> usize {
+        self.ranges.iter().map(|r| r.len()).sum()
+    }
+
+    pub fn min(&self) -> Option<usize> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    pub fn contains(&self, v: usize) -> bool {
+        self.ranges.binary_search_by(|r| order(r, v)).is_ok()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::new(self.ranges.iter().chain(&other.ranges).cloned())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let (mut a, mut b) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            let overlap = intersect(x, y);
+            if !overlap.is_empty() {
+                ranges.push(overlap);
+            }
+            if x.end <= y.end {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let mut others = other.ranges.iter().peekable();
+        for range in &self.ranges {
+            let mut start = range.start;
+            while let Some(o) = others.peek() {
+                if o.end <= start {
+                    others.next();
+                    continue;
+                }
+                if o.start >= range.end {
+                    break;
+                }
+                if o.start > start {
+                    ranges.push(start..o.start);
+                }
+                start = o.end;
+                if o.end > range.end {
+                    break;
+                }
+                others.next();
+            }
+            if start < range.end {
+                ranges.push(start..range.end);
+            }
+        }
+        Self { ranges }
+    }
+}
+
+impl From<Range<usize>> for IntervalSet {
+    fn from(range: Range<usize>) -> Self {
+        Self::new([range])
+    }
+}
+
+fn intersect(a: &Range<usize>, b: &Range<usize>) -> Range<usize> {
+    a.start.max(b.start)..a.end.min(b.end)
+}
+
+/// Where `v` is relative to `range`, for binary searches
+fn order(range: &Range<usize>, v: usize) -> Ordering {
+    if range.start > v {
+        Ordering::Greater
+    } else if v >= range.end {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MapEntry {
     dest_start: usize,
     src: Range<usize>,
 }
 
+impl MapEntry {
+    /// The entry of an almanac line, if its source and destination end in `usize`
+    ///
+    /// Then shifting any part of the source stays in `usize` as well.
+    pub fn new(dest_start: usize, src_start: usize, len: usize) -> Option<Self> {
+        dest_start.checked_add(len)?;
+        Some(Self {
+            dest_start,
+            src: src_start..src_start.checked_add(len)?,
+        })
+    }
+
+    fn dest(&self) -> Range<usize> {
+        self.dest_start..self.dest_start + self.src.len()
+    }
+
+    /// Image of `range`, which is part of the source
+    fn apply(&self, range: &Range<usize>) -> Range<usize> {
+        let start = range.start - self.src.start + self.dest_start;
+        start..start + range.len()
+    }
+
+    /// Source of `range`, which is part of the destination
+    fn revert(&self, range: &Range<usize>) -> Range<usize> {
+        let start = range.start - self.dest_start + self.src.start;
+        start..start + range.len()
+    }
+
+    fn is_identity(&self) -> bool {
+        self.dest_start == self.src.start
+    }
+}
+
 impl From<(usize, usize, usize)> for MapEntry {
     fn from((dest_start, src_start, len): (usize, usize, usize)) -> Self {
         Self {
@@ -24,16 +215,119 @@ impl From<(usize, usize, usize)> for MapEntry {
     }
 }
 
+/// Piecewise shift of numbers, numbers outside of all entries stay the same
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct RangeMap {
     entries: Vec<MapEntry>,
 }
 
 impl RangeMap {
-    fn new(mut entries: Vec<MapEntry>) -> Self {
+    /// Entries must not overlap in their sources
+    pub fn new(mut entries: Vec<MapEntry>) -> Self {
         entries.sort_by_key(|e| e.src.start);
         Self { entries }
     }
 
+    /// The map leaving every number as it is
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    /// Entries covering `0..usize::MAX`, with identity entries in the gaps
+    pub fn filled(&self) -> Vec<MapEntry> {
+        let mut pieces = vec![];
+        let mut start = 0;
+        for entry in &self.entries {
+            if start < entry.src.start {
+                pieces.push(MapEntry::from((start, start, entry.src.start - start)));
+            }
+            pieces.push(entry.clone());
+            start = entry.src.end;
+        }
+        if start < usize::MAX {
+            pieces.push(MapEntry::from((start, start, usize::MAX - start)));
+        }
+        pieces
+    }
+
+    /// Joins adjacent pieces with the same shift and drops identity pieces
+    fn simplified(pieces: impl IntoIterator<Item = MapEntry>) -> Self {
+        let mut entries: Vec<MapEntry> = vec![];
+        for piece in pieces.into_iter().filter(|p| !p.src.is_empty()) {
+            match entries.last_mut() {
+                Some(last)
+                    if last.src.end == piece.src.start && last.dest().end == piece.dest_start =>
+                {
+                    last.src.end = piece.src.end
+                }
+                _ => entries.push(piece),
+            }
+        }
+        entries.retain(|e| !e.is_identity());
+        Self { entries }
+    }
+
+    /// The map applying `self` and then `next`
+    pub fn then(&self, next: &RangeMap) -> RangeMap {
+        let next = next.filled();
+        let mut pieces = vec![];
+        for piece in self.filled() {
+            let image = piece.dest();
+            for after in &next {
+                let overlap = intersect(&image, &after.src);
+                if !overlap.is_empty() {
+                    let src = piece.revert(&overlap);
+                    pieces.push(MapEntry::from((
+                        after.apply(&overlap).start,
+                        src.start,
+                        src.len(),
+                    )));
+                }
+            }
+        }
+        Self::simplified(pieces)
+    }
+
+    /// The reverse map, if no two numbers are mapped to the same one
+    pub fn invert(&self) -> Option<RangeMap> {
+        let mut pieces = self
+            .filled()
+            .into_iter()
+            .map(|p| MapEntry::from((p.src.start, p.dest_start, p.src.len())))
+            .collect::<Vec<_>>();
+        pieces.sort_by_key(|p| p.src.start);
+        let mut end = 0;
+        for piece in &pieces {
+            if piece.src.start != end {
+                return None;
+            }
+            end = piece.src.end;
+        }
+        (end == usize::MAX).then(|| Self::simplified(pieces))
+    }
+
+    /// Numbers that `set` is mapped to
+    pub fn image(&self, set: &IntervalSet) -> IntervalSet {
+        let pieces = self.filled();
+        IntervalSet::new(set.ranges().iter().flat_map(|range| {
+            pieces.iter().filter_map(move |p| {
+                let overlap = intersect(range, &p.src);
+                (!overlap.is_empty()).then(|| p.apply(&overlap))
+            })
+        }))
+    }
+
+    /// Numbers that are mapped into `set`
+    pub fn preimage(&self, set: &IntervalSet) -> IntervalSet {
+        let pieces = self.filled();
+        IntervalSet::new(set.ranges().iter().flat_map(|range| {
+            pieces.iter().filter_map(move |p| {
+                let overlap = intersect(range, &p.dest());
+                (!overlap.is_empty()).then(|| p.revert(&overlap))
+            })
+        }))
+    }
+
     pub fn transform_list(&self, ranges: &mut [Range<usize>]) -> Vec<Range<usize>> {
         ranges.sort_by_key(|r| r.start);
         let mut map_entries = self.entries.iter().cloned().peekable();
@@ -52,18 +346,17 @@ impl RangeMap {
                     } else if next_map_entry.src.start <= range.start {
                         // Range starts within entry
                         let start =
-                            range.start + next_map_entry.dest_start - next_map_entry.src.start;
+                            range.start - next_map_entry.src.start + next_map_entry.dest_start;
                         if range.end <= next_map_entry.src.end {
                             // Range fully in map entry
                             let end =
-                                range.end + next_map_entry.dest_start - next_map_entry.src.start;
+                                range.end - next_map_entry.src.start + next_map_entry.dest_start;
                             // Consume and keep entry in iter
                             result.push(start..end);
                             break;
                         } else {
                             // Range ends after entry
-                            let end = next_map_entry.src.end + next_map_entry.dest_start
-                                - next_map_entry.src.start;
+                            let end = next_map_entry.dest().end;
                             // Consume and keep entry in iter
                             result.push(start..end);
                             // Reset range start
@@ -82,11 +375,9 @@ impl RangeMap {
                             range.start = next_map_entry.src.start;
                             continue; // do here?
                         } else {
-                            let start = next_map_entry.dest_start;
-                            let end = next_map_entry.dest_start + next_map_entry.src.len();
                             range.start = next_map_entry.src.end;
+                            result.push(next_map_entry.dest());
                             map_entries.next();
-                            result.push(start..end);
                             continue;
                         }
                     }
@@ -100,60 +391,185 @@ impl RangeMap {
         result
     }
 
-    fn transform(&self, v: usize) -> usize {
-        if let Ok(index) = self.entries.binary_search_by(|e| {
-            if e.src.start > v {
-                Ordering::Greater
-            } else if v >= e.src.end {
-                Ordering::Less
-            } else {
-                Ordering::Equal
+    pub fn transform(&self, v: usize) -> usize {
+        match self.entries.binary_search_by(|e| order(&e.src, v)) {
+            Ok(index) => {
+                let e = &self.entries[index];
+                v - e.src.start + e.dest_start
             }
-        }) {
-            let e = &self.entries[index];
-            v + e.dest_start - e.src.start
-        } else {
-            v
+            Err(_) => v,
         }
     }
 }
 
+/// A map from one category of the almanac to another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryMap {
+    pub source: String,
+    pub destination: String,
+    pub ranges: RangeMap,
+}
+
+/// Maps to apply in order to convert numbers of one category to another
+pub struct Conversion<'a> {
+    steps: Vec<Cow<'a, RangeMap>>,
+}
+
+impl Conversion<'_> {
+    pub fn transform(&self, v: usize) -> usize {
+        self.steps.iter().fold(v, |v, map| map.transform(v))
+    }
+
+    /// `v` and the numbers it is converted to in every step
+    pub fn transform_debug(&self, v: usize) -> Vec<usize> {
+        let mut steps = vec![v];
+        for map in &self.steps {
+            steps.push(map.transform(*steps.last().unwrap()));
+        }
+        steps
+    }
+
+    pub fn transform_list(&self, mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+        for map in &self.steps {
+            ranges = map.transform_list(&mut ranges);
+        }
+        ranges
+    }
+
+    /// All steps folded into one map
+    pub fn composed(&self) -> RangeMap {
+        self.steps
+            .iter()
+            .fold(RangeMap::identity(), |map, next| map.then(next))
+    }
+}
+
 pub struct Input {
     pub seeds: Vec<usize>,
-    pub maps: Vec<RangeMap>,
+    pub maps: Vec<CategoryMap>,
 }
 
 impl Input {
-    fn new(seeds: Vec<usize>, maps: Vec<RangeMap>) -> Input {
+    fn new(seeds: Vec<usize>, maps: Vec<CategoryMap>) -> Input {
         Input { seeds, maps }
     }
 
-    pub fn transform(&self, seed: usize) -> usize {
-        let mut v = seed;
-        for (_i, map) in self.maps.iter().enumerate() {
-            v = map.transform(v);
+    /// Categories in the order they first appear
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories = vec![];
+        for map in &self.maps {
+            for name in [&map.source, &map.destination] {
+                if !categories.contains(&name.as_str()) {
+                    categories.push(name.as_str());
+                }
+            }
         }
-        v
+        categories
     }
 
-    pub fn transform_debug(&self, seed: usize) -> Vec<usize> {
-        let mut v = seed;
-        let mut steps = vec![v];
-        for (_i, map) in self.maps.iter().enumerate() {
-            v = map.transform(v);
-            steps.push(v);
+    /// How to convert `from` to `to`, going through maps in either direction
+    ///
+    /// Fails if a category is unknown, if there is no way or more than one way
+    /// between them, or if a map on the way has to be inverted but cannot be.
+    pub fn conversion(&self, from: &str, to: &str) -> Result<Conversion<'_>> {
+        for name in [from, to] {
+            if !self.categories().contains(&name) {
+                let reason = format!("unknown category {name:?}");
+                return Err(Error::unsolvable(DAY, reason));
+            }
         }
-        steps
+        let mut paths = vec![];
+        self.paths(&mut vec![from], to, &mut vec![], &mut paths);
+        let path = match paths.len() {
+            0 => {
+                let reason = format!("no maps lead from {from} to {to}");
+                return Err(Error::unsolvable(DAY, reason));
+            }
+            1 => paths.pop().unwrap(),
+            _ => {
+                let reason = format!("more than one way leads from {from} to {to}");
+                return Err(Error::unsolvable(DAY, reason));
+            }
+        };
+        let steps = path
+            .into_iter()
+            .map(|(index, forward)| {
+                let map = &self.maps[index];
+                if forward {
+                    return Ok(Cow::Borrowed(&map.ranges));
+                }
+                let inverse = map.ranges.invert().ok_or_else(|| {
+                    let reason = format!(
+                        "the {}-to-{} map cannot be inverted",
+                        map.source, map.destination
+                    );
+                    Error::unsolvable(DAY, reason)
+                })?;
+                Ok(Cow::Owned(inverse))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Conversion { steps })
     }
 
-    pub fn lowest_location(&self) -> Option<usize> {
+    /// Collects up to two paths to `to` that visit no category twice
+    ///
+    /// A path is a list of maps, each with whether it is applied forward.
+    fn paths<'a>(
+        &'a self,
+        visited: &mut Vec<&'a str>,
+        to: &str,
+        path: &mut Vec<(usize, bool)>,
+        paths: &mut Vec<Vec<(usize, bool)>>,
+    ) {
+        let here = *visited.last().unwrap();
+        if here == to {
+            paths.push(path.clone());
+            return;
+        }
+        for (index, map) in self.maps.iter().enumerate() {
+            for (forward, source, destination) in [
+                (true, &map.source, &map.destination),
+                (false, &map.destination, &map.source),
+            ] {
+                if paths.len() > 1 || source != here || visited.contains(&destination.as_str()) {
+                    continue;
+                }
+                visited.push(destination);
+                path.push((index, forward));
+                self.paths(visited, to, path, paths);
+                path.pop();
+                visited.pop();
+            }
+        }
+    }
+
+    /// The map between any two categories
+    pub fn convert(&self, from: &str, to: &str) -> Result<RangeMap> {
+        Ok(self.conversion(from, to)?.composed())
+    }
+
+    fn seed_to_location(&self) -> Result<Conversion<'_>> {
+        self.conversion("seed", "location")
+    }
+
+    pub fn transform(&self, seed: usize) -> Result<usize> {
+        Ok(self.seed_to_location()?.transform(seed))
+    }
+
+    pub fn transform_debug(&self, seed: usize) -> Result<Vec<usize>> {
+        Ok(self.seed_to_location()?.transform_debug(seed))
+    }
+
+    pub fn lowest_location(&self) -> Result<usize> {
+        let conversion = self.seed_to_location()?;
         self.seeds
             .iter()
-            .copied()
-            .map(|seed| self.transform(seed))
+            .map(|&seed| conversion.transform(seed))
             .min()
+            .ok_or(Error::unsolvable(DAY, "no seeds"))
     }
 
+    /// Seed ranges of part 2, the parser checks that they fit in `usize`
     pub fn ranges(&self) -> impl Iterator<Item = Range<usize>> + '_ {
         self.seeds.chunks_exact(2).map(|range| {
             let start = range[0];
@@ -162,70 +578,175 @@ impl Input {
         })
     }
 
-    pub fn lowest_location_part2_alt(&self) -> Option<usize> {
-        let mut ranges: Vec<_> = self.ranges().collect();
-        for map in &self.maps {
-            ranges = map.transform_list(&mut ranges);
-        }
-        ranges.iter().map(|x| x.start).min()
+    /// Seed ranges of part 2
+    pub fn seed_ranges(&self) -> IntervalSet {
+        IntervalSet::new(self.ranges())
+    }
+
+    /// All maps folded into one from seed to location
+    pub fn composed(&self) -> Result<RangeMap> {
+        self.convert("seed", "location")
+    }
+
+    pub fn lowest_location_composed(&self) -> Result<usize> {
+        self.composed()?
+            .image(&self.seed_ranges())
+            .min()
+            .ok_or(Error::unsolvable(DAY, "no seed ranges"))
     }
 
-    pub fn lowest_location_part2(&self) -> Option<usize> {
+    /// Seeds that end up in `locations`
+    pub fn seeds_in(&self, locations: &IntervalSet) -> Result<IntervalSet> {
+        Ok(self.composed()?.preimage(locations))
+    }
+
+    pub fn lowest_location_part2_alt(&self) -> Result<usize> {
+        let ranges = self
+            .seed_to_location()?
+            .transform_list(self.ranges().collect());
+        ranges
+            .iter()
+            .map(|x| x.start)
+            .min()
+            .ok_or(Error::unsolvable(DAY, "no seed ranges"))
+    }
+
+    pub fn lowest_location_part2(&self) -> Result<usize> {
+        let conversion = self.seed_to_location()?;
         self.ranges()
-            .map(|range| range.map(|seed| self.transform(seed)).min().unwrap())
+            .filter_map(|range| range.map(|seed| conversion.transform(seed)).min())
             .min()
+            .ok_or(Error::unsolvable(DAY, "no seed ranges"))
     }
 }
 
-fn parse_num(input: &str) -> IResult<&str, usize> {
+/// Results of the parsers, whose errors say what was expected in their context
+type PResult<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
+
+/// Stops parsing at `input`, which is not `expected`
+fn failure<'a>(input: &'a str, expected: &'static str) -> nom::Err<VerboseError<&'a str>> {
+    nom::Err::Failure(VerboseError {
+        errors: vec![(input, VerboseErrorKind::Context(expected))],
+    })
+}
+
+fn parse_num(input: &str) -> PResult<'_, usize> {
     map_res(digit1, <usize as FromStr>::from_str)(input)
 }
 
-pub fn parse_input_seeds(input: &str) -> IResult<&str, Vec<usize>> {
-    let p2 = separated_list0(tag(" "), parse_num);
-    let mut p3 = preceded(tag("seeds: "), p2);
-    let (input, seeds) = p3(input)?;
-    Ok((input, seeds))
+pub fn parse_input_seeds(input: &str) -> PResult<'_, Vec<usize>> {
+    let p2 = separated_list0(tag(" "), consumed(parse_num));
+    let mut p3 = context("a list of seeds", preceded(tag("seeds: "), p2));
+    let (rest, seeds) = p3(input)?;
+    let offset = |part: &str| part.as_ptr() as usize - input.as_ptr() as usize;
+    for pair in seeds.chunks(2) {
+        match pair {
+            [(start_text, start), (len_text, len)] => {
+                if start.checked_add(*len).is_none() {
+                    let range = &input[offset(start_text)..offset(len_text) + len_text.len()];
+                    return Err(failure(range, "a seed range that fits in usize"));
+                }
+            }
+            [(start_text, _)] => return Err(failure(start_text, "a seed with a range length")),
+            _ => unreachable!(),
+        }
+    }
+    Ok((rest, seeds.into_iter().map(|(_, seed)| seed).collect()))
 }
 
-fn parse_space_num(input: &str) -> IResult<&str, usize> {
+fn parse_space_num(input: &str) -> PResult<'_, usize> {
     preceded(tag(" "), parse_num)(input)
 }
 
-pub fn parse_triple(input: &str) -> IResult<&str, MapEntry> {
-    map(
+pub fn parse_triple(input: &str) -> PResult<'_, MapEntry> {
+    let (rest, (dest_start, src_start, len)) = context(
+        "a map entry",
         tuple((parse_num, parse_space_num, parse_space_num)),
-        MapEntry::from,
-    )(input)
+    )(input)?;
+    let entry = MapEntry::new(dest_start, src_start, len)
+        .ok_or_else(|| failure(input, "a map entry whose ranges fit in usize"))?;
+    Ok((rest, entry))
 }
 
-pub fn parse_map_title(input: &str) -> IResult<&str, (&str, &str)> {
-    pair(
-        terminated(alpha1, tag("-to-")),
-        terminated(alpha1, tag(" map:")),
+pub fn parse_map_title(input: &str) -> PResult<'_, (&str, &str)> {
+    context(
+        "a map title",
+        pair(
+            terminated(alpha1, tag("-to-")),
+            terminated(alpha1, tag(" map:")),
+        ),
     )(input)
 }
 
-pub fn parse_map(input: &str) -> IResult<&str, RangeMap> {
-    map(
-        preceded(parse_map_title, many1(preceded(tag("\n"), parse_triple))),
-        RangeMap::new,
-    )(input)
+pub fn parse_map(input: &str) -> PResult<'_, CategoryMap> {
+    let (rest, ((source, destination), mut entries)) = pair(
+        parse_map_title,
+        many1(preceded(tag("\n"), consumed(parse_triple))),
+    )(input)?;
+    entries.sort_by_key(|(_, entry)| entry.src.start);
+    for pair in entries.windows(2) {
+        if pair[0].1.src.end > pair[1].1.src.start {
+            // blame the later line
+            let line = pair[0].0.max(pair[1].0);
+            return Err(failure(line, "a map entry not overlapping the others"));
+        }
+    }
+    let entries = entries.into_iter().map(|(_, entry)| entry).collect();
+    let map = CategoryMap {
+        source: source.to_owned(),
+        destination: destination.to_owned(),
+        ranges: RangeMap::new(entries),
+    };
+    Ok((rest, map))
 }
 
-pub fn parse_input(input: &str) -> IResult<&str, Input> {
+pub fn parse_input(input: &str) -> PResult<'_, Input> {
     map(
-        pair(parse_input_seeds, many1(preceded(tag("\n\n"), parse_map))),
+        pair(
+            parse_input_seeds,
+            many1(preceded(context("a map title", tag("\n\n")), parse_map)),
+        ),
         |(a, b)| Input::new(a, b),
     )(input)
 }
 
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or(text)
+}
+
+/// Parses a complete almanac
+pub fn parse(text: &str) -> Result<Input> {
+    let (rest, input) = parse_input(text).map_err(|e| match e {
+        nom::Err::Error(e) | nom::Err::Failure(e) => {
+            // the innermost context is the most specific
+            let (part, expected) = e
+                .errors
+                .iter()
+                .find_map(|(part, kind)| match kind {
+                    VerboseErrorKind::Context(expected) => Some((*part, *expected)),
+                    _ => None,
+                })
+                .unwrap_or((text, "an almanac"));
+            ParseError::at(DAY, text, first_line(part), expected)
+        }
+        nom::Err::Incomplete(_) => ParseError::at(DAY, text, &text[text.len()..], "more input"),
+    })?;
+    let rest = rest.trim_start();
+    if !rest.is_empty() {
+        return Err(ParseError::at(DAY, text, first_line(rest), "a map entry or map").into());
+    }
+    Ok(input)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day5::MapEntry;
+    use crate::day5::{IntervalSet, MapEntry, RangeMap};
 
-    use super::parse_input_seeds;
     use super::parse_triple;
+    use super::{parse_input_seeds, DAY};
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day5/example.txt");
 
     #[test]
     fn parse_seed_line() {
@@ -256,7 +777,11 @@ mod tests {
         let text = "seed-to-soil map:\n50 98 2\n52 50 48";
         let (_rest, map) = super::parse_map(text).unwrap();
         assert_eq!(
-            map.entries,
+            (map.source.as_str(), map.destination.as_str()),
+            ("seed", "soil")
+        );
+        assert_eq!(
+            map.ranges.entries,
             vec![
                 MapEntry {
                     dest_start: 52,
@@ -272,46 +797,206 @@ mod tests {
 
     #[test]
     fn parse_example() {
-        let text = std::fs::read_to_string("res/day5/example.txt").unwrap();
-        let (_rest, input) = super::parse_input(&text).unwrap();
+        let input = super::parse(EXAMPLE).unwrap();
         assert_eq!(&input.seeds, &[79, 14, 55, 13]);
         assert_eq!(input.maps.len(), 7);
 
         assert_eq!(input.seeds[0], 79);
         assert_eq!(
-            input.transform_debug(79),
+            input.transform_debug(79).unwrap(),
             vec![79, 81, 81, 81, 74, 78, 78, 82]
         );
-        assert_eq!(input.transform(input.seeds[1]), 43);
-        assert_eq!(input.transform(input.seeds[2]), 86);
-        assert_eq!(input.transform(input.seeds[3]), 35);
+        assert_eq!(input.transform(input.seeds[1]).unwrap(), 43);
+        assert_eq!(input.transform(input.seeds[2]).unwrap(), 86);
+        assert_eq!(input.transform(input.seeds[3]).unwrap(), 35);
 
-        assert_eq!(input.lowest_location(), Some(35));
-        assert_eq!(input.lowest_location_part2(), Some(46));
+        assert_eq!(input.lowest_location().unwrap(), 35);
+        assert_eq!(input.lowest_location_part2().unwrap(), 46);
     }
 
     #[test]
+    fn parse_error() {
+        let text = "seeds: 79 14\n\nseed-to-soil map:\n50 98 2\n52 x 48\n";
+        let e = super::parse(text).err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "day 5, line 5, column 1: expected a map entry or map, found \"52 x 48\""
+        );
+
+        let e = super::parse("seeds: 1 2").err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "day 5, line 1, column 11: expected a map title, found end of input"
+        );
+        let e = super::parse("seeds: 1 2\n\nseed-to-soil:\n1 2 3\n")
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.to_string(),
+            "day 5, line 3, column 1: expected a map title, found \"seed-to-soil:\""
+        );
+        let e = super::parse("seeds: 1 2\n\na-to-b map:\n1 2\n")
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.to_string(),
+            "day 5, line 4, column 1: expected a map entry, found \"1 2\""
+        );
+        let e = super::parse("seed: 1\n").err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "day 5, line 1, column 1: expected a list of seeds, found \"seed: 1\""
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let error = |text: &str| super::parse(text).err().unwrap().to_string();
+        let max = usize::MAX;
+        assert_eq!(
+            error(&format!("seeds: {max} 1\n\na-to-b map:\n0 0 1\n")),
+            format!("day 5, line 1, column 8: expected a seed range that fits in usize, found \"{max} 1\"")
+        );
+        assert_eq!(
+            error("seeds: 1 2 3\n\na-to-b map:\n0 0 1\n"),
+            "day 5, line 1, column 12: expected a seed with a range length, found \"3\""
+        );
+        assert_eq!(
+            error(&format!("seeds: 1 2\n\na-to-b map:\n0 0 1\n0 {max} 2\n")),
+            format!("day 5, line 5, column 1: expected a map entry whose ranges fit in usize, found \"0 {max} 2\"")
+        );
+        assert_eq!(
+            error("seeds: 1 2\n\na-to-b map:\n0 5 3\n10 2 4\n20 9 1\n"),
+            "day 5, line 5, column 1: expected a map entry not overlapping the others, found \"10 2 4\""
+        );
+    }
+
+    #[test]
+    fn ends_of_usize() {
+        let max = usize::MAX;
+        let text = format!("seeds: 0 2\n\nseed-to-location map:\n0 0 1\n{max} 1 1\n");
+        assert_eq!(
+            super::parse(&text).err().unwrap().to_string(),
+            format!("day 5, line 5, column 1: expected a map entry whose ranges fit in usize, found \"{max} 1 1\"")
+        );
+
+        // the last number `usize` has is never part of a range
+        let last = max - 1;
+        let text =
+            format!("seeds: 0 2 {last} 1\n\nseed-to-location map:\n{last} 0 1\n0 {last} 1\n");
+        let input = super::parse(&text).unwrap();
+        let map = &input.maps[0].ranges;
+        let inverse = map.invert().unwrap();
+        assert_eq!(map.then(&inverse), RangeMap::identity());
+        assert_eq!(map.then(map), RangeMap::identity());
+        assert_eq!(map.filled().len(), 3);
+        assert_eq!(input.lowest_location().unwrap(), 0);
+        assert_eq!(input.lowest_location_composed().unwrap(), 0);
+        assert_eq!(input.lowest_location_part2_alt().unwrap(), 0);
+        assert_eq!(input.lowest_location_part2().unwrap(), 0);
+    }
+
+    #[test]
+    #[cfg_attr(not(input = "day5"), ignore = "res/day5/input.txt is missing")]
     fn part1() {
         let text = std::fs::read_to_string("res/day5/input.txt").unwrap();
-        let (_rest, input) = super::parse_input(&text).unwrap();
+        let input = super::parse(&text).unwrap();
         assert_eq!(input.maps.len(), 7);
 
-        assert_eq!(input.lowest_location(), Some(51752125));
+        let lowest = input.lowest_location().unwrap();
+        assert_eq!(lowest.to_string(), expected(DAY, 1));
     }
 
     #[test]
     fn part2_example() {
-        let text = std::fs::read_to_string("res/day5/example.txt").unwrap();
-        let (_rest, input) = super::parse_input(&text).unwrap();
-        assert_eq!(input.lowest_location_part2(), Some(46));
-        assert_eq!(input.lowest_location_part2_alt(), Some(46));
+        let input = super::parse(EXAMPLE).unwrap();
+        assert_eq!(input.lowest_location_part2().unwrap(), 46);
+        assert_eq!(input.lowest_location_part2_alt().unwrap(), 46);
+        assert_eq!(input.lowest_location_composed().unwrap(), 46);
+    }
+
+    #[test]
+    fn intervals() {
+        let a = IntervalSet::new([5..10, 0..3, 2..4, 10..12]);
+        assert_eq!(a.ranges(), [0..4, 5..12]);
+        let b = IntervalSet::new([3..6, 8..9, 11..20]);
+        assert_eq!(a.union(&b), IntervalSet::from(0..20));
+        assert_eq!(a.intersection(&b).ranges(), [3..4, 5..6, 8..9, 11..12]);
+        assert_eq!(a.difference(&b).ranges(), [0..3, 6..8, 9..11]);
+        assert_eq!(b.difference(&a).ranges(), [4..5, 12..20]);
+        assert_eq!((a.len(), a.min()), (11, Some(0)));
+        assert!(a.contains(11) && !a.contains(4));
+    }
+
+    #[test]
+    fn algebra() {
+        let input = super::parse(EXAMPLE).unwrap();
+        let almanac = input.composed().unwrap();
+        for seed in 0..120 {
+            assert_eq!(almanac.transform(seed), input.transform(seed).unwrap());
+        }
+        let every = IntervalSet::from(0..120);
+        let seeds = input.seeds_in(&IntervalSet::from(46..47)).unwrap();
+        assert!(seeds
+            .ranges()
+            .iter()
+            .cloned()
+            .flatten()
+            .all(|s| input.transform(s).unwrap() == 46));
+        assert!(seeds.contains(82));
+        assert_eq!(
+            every.intersection(&seeds).len(),
+            (0..120)
+                .filter(|&s| input.transform(s).unwrap() == 46)
+                .count()
+        );
+
+        // seed-to-soil of the example swaps nothing into place twice
+        let map = &input.maps[0].ranges;
+        let inverse = map.invert().unwrap();
+        assert_eq!(map.then(&inverse), RangeMap::identity());
+        assert_eq!(inverse.transform(52), 50);
+        assert_eq!(
+            RangeMap::new(vec![MapEntry::from((0, 5, 1))]).invert(),
+            None
+        );
+        assert_eq!(map.filled().len(), 4);
+    }
+
+    #[test]
+    fn categories() {
+        let input = super::parse(EXAMPLE).unwrap();
+        assert_eq!(input.categories().len(), 8);
+        let soil = input.convert("humidity", "soil").unwrap();
+        for seed in 0..120 {
+            let humidity = input
+                .conversion("seed", "humidity")
+                .unwrap()
+                .transform(seed);
+            assert_eq!(
+                soil.transform(humidity),
+                input.maps[0].ranges.transform(seed)
+            );
+        }
+        assert_eq!(input.convert("soil", "soil").unwrap(), RangeMap::identity());
+
+        let err = |from, to| input.conversion(from, to).err().unwrap().to_string();
+        assert_eq!(err("seed", "gold"), "day 5: unknown category \"gold\"");
+
+        let text = "seeds: 1 1\n\na-to-b map:\n0 1 1\n\nc-to-d map:\n0 1 1\n\nb-to-a map:\n5 5 1";
+        let input = super::parse(text).unwrap();
+        let err = |from, to| input.conversion(from, to).err().unwrap().to_string();
+        assert_eq!(err("a", "c"), "day 5: no maps lead from a to c");
+        assert_eq!(err("a", "b"), "day 5: more than one way leads from a to b");
+        assert_eq!(err("d", "c"), "day 5: the c-to-d map cannot be inverted");
     }
 
     #[test]
+    #[cfg_attr(not(input = "day5"), ignore = "res/day5/input.txt is missing")]
     fn part2() {
         let text = std::fs::read_to_string("res/day5/input.txt").unwrap();
-        let (_rest, input) = super::parse_input(&text).unwrap();
-        assert_eq!(input.lowest_location_part2_alt(), Some(12634632));
-        //assert_eq!(input.lowest_location_part2(), Some(12634632));
+        let input = super::parse(&text).unwrap();
+        let lowest = input.lowest_location_part2_alt().unwrap();
+        assert_eq!(lowest.to_string(), expected(DAY, 2));
     }
 }
@@ -1,77 +1,204 @@
-use std::f32::EPSILON;
+use std::path::Path;
 
-/// d = s * (t - s)
-///
-/// => -s^2 + ts = d
-/// => s^2 - ts = -d
-/// => s^2 - ts + d = 0
-///
-/// p = -t
-/// q = d
-///
-/// x0 = t/2 +/- sqrt((-t/2)^2 - d)
-fn solve_for_s(t: f32, d: f32) -> Option<(f32, f32)> {
-    let t2 = t / 2.0;
-    let in_root = (t * t) / 4.0 - d;
-    if in_root > 0.0 {
-        let root = in_root.sqrt();
-        Some(((t2 - root), (t2 + root)))
-    } else {
-        None
+use num::integer::Roots;
+
+use crate::{
+    error::{ParseError, Result},
+    solution::Solution,
+};
+
+const DAY: usize = 6;
+
+pub struct Day6;
+
+impl Solution for Day6 {
+    type Input = Sheet;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        Ok(input
+            .races
+            .iter()
+            .map(|&(t, d)| num_ways_to_win(t, d))
+            .product())
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        let (t, d) = input.race;
+        Ok(num_ways_to_win(t, d))
+    }
+}
+
+/// Both readings of a race sheet, as (time, distance) pairs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sheet {
+    /// Every column is a race
+    pub races: Vec<(usize, usize)>,
+    /// The kerning is ignored, so each line is one number
+    pub race: (usize, usize),
+}
+
+pub fn run(path: &Path) -> Result<(usize, usize)> {
+    let sheet = parse(&std::fs::read_to_string(path)?)?;
+    Ok((Day6::part1(&sheet)?, Day6::part2(&sheet)?))
+}
+
+/// Parses the Time and Distance lines in both readings
+pub fn parse(text: &str) -> Result<Sheet> {
+    Ok(Sheet {
+        races: parse_races(text)?,
+        race: parse_race(text)?,
+    })
+}
+
+/// Reads every column of the sheet as a race
+pub fn parse_races(text: &str) -> Result<Vec<(usize, usize)>> {
+    let mut lines = text.lines();
+    let times = parse_input_line(text, lines.next(), "Time:")?;
+    let distances = parse_input_line(text, lines.next(), "Distance:")?;
+    if times.len() != distances.len() {
+        let line = text.lines().nth(1).unwrap_or_default();
+        let expected = format!("{} distances", times.len());
+        return Err(ParseError::at(DAY, text, line, expected).into());
     }
+    Ok(times.into_iter().zip(distances).collect())
+}
+
+/// Reads the sheet as one race, ignoring the spaces between the digits
+pub fn parse_race(text: &str) -> Result<(usize, usize)> {
+    let mut lines = text.lines();
+    let time = parse_kerned_line(text, lines.next(), "Time:")?;
+    let distance = parse_kerned_line(text, lines.next(), "Distance:")?;
+    Ok((time, distance))
+}
+
+fn strip_line<'a>(text: &'a str, line: Option<&'a str>, prefix: &'static str) -> Result<&'a str> {
+    let end = &text[text.len()..];
+    let line = line.ok_or_else(|| ParseError::at(DAY, text, end, prefix))?;
+    Ok(line
+        .strip_prefix(prefix)
+        .ok_or_else(|| ParseError::at(DAY, text, line, prefix))?)
+}
+
+fn parse_input_line(text: &str, line: Option<&str>, prefix: &'static str) -> Result<Vec<usize>> {
+    strip_line(text, line, prefix)?
+        .split(' ')
+        .filter(|f| !f.is_empty())
+        .map(|f| {
+            f.parse::<usize>()
+                .map_err(|_| ParseError::at(DAY, text, f, "a number").into())
+        })
+        .collect()
+}
+
+fn parse_kerned_line(text: &str, line: Option<&str>, prefix: &'static str) -> Result<usize> {
+    let numbers = strip_line(text, line, prefix)?.trim();
+    let digits = numbers.split(' ').collect::<String>();
+    digits.parse().map_err(|_| {
+        let expected = if digits.bytes().all(|b| b.is_ascii_digit()) {
+            "a number that fits in usize"
+        } else {
+            "a number"
+        };
+        ParseError::at(DAY, text, numbers, expected).into()
+    })
+}
+
+/// Distance travelled when holding the button for `s` of the `t` milliseconds
+fn distance(t: usize, s: usize) -> u128 {
+    s as u128 * (t - s) as u128
 }
 
+/// Shortest and longest holds that beat the record `d` in a race of `t` ms
+///
+/// d = s * (t - s) has the roots s = (t +/- sqrt(t^2 - 4d)) / 2, and the
+/// holds strictly between them win. Holds exactly on a root only tie the
+/// record, so they do not count. The integer square root gets within one of
+/// the shortest hold, which is then corrected by checking the distance; the
+/// longest hold is symmetric to it.
 pub fn min_max(t: usize, d: usize) -> Option<(usize, usize)> {
-    let e = 10.0 * EPSILON;
-    solve_for_s(t as f32, d as f32)
-        .map(|(s_min, s_max)| ((s_min + e).ceil() as usize, (s_max - e).floor() as usize))
+    let discriminant = (t as u128 * t as u128).checked_sub(4 * d as u128)?;
+    let root = discriminant.sqrt() as usize;
+    let mut min = (t - root) / 2;
+    while min <= t / 2 && distance(t, min) <= d as u128 {
+        min += 1;
+    }
+    while min > 0 && distance(t, min - 1) > d as u128 {
+        min -= 1;
+    }
+    (min <= t / 2).then(|| (min, t - min))
 }
 
 pub fn num_ways_to_win(t: usize, d: usize) -> usize {
-    if let Some((min, max)) = min_max(t, d) {
-        if max >= min {
-            return max + 1 - min;
-        }
-    }
-    0
+    min_max(t, d).map_or(0, |(min, max)| max + 1 - min)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day6::{min_max, num_ways_to_win};
+    use std::path::Path;
+
+    use crate::{
+        day6::{min_max, num_ways_to_win},
+        solution::Solution,
+    };
 
-    use super::solve_for_s;
+    use super::{Day6, DAY};
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day6/example.txt");
 
     #[test]
     fn example() {
-        assert_eq!(solve_for_s(7.0, 9.0), Some((1.6972244, 5.3027754)));
+        let sheet = super::parse(EXAMPLE).unwrap();
+        assert_eq!(sheet.races, [(7, 9), (15, 40), (30, 200)]);
+        assert_eq!(sheet.race, (71530, 940200));
+        assert_eq!(Day6::part1(&sheet).unwrap(), 288);
+        assert_eq!(Day6::part2(&sheet).unwrap(), 71503);
+
+        assert_eq!(min_max(7, 9), Some((2, 5)));
         assert_eq!(num_ways_to_win(7, 9), 4);
         assert_eq!(num_ways_to_win(15, 40), 8);
-        assert_eq!(solve_for_s(30.0, 200.0), Some((10.0, 20.0)));
+        // 10 and 20 only tie the record
+        assert_eq!(min_max(30, 200), Some((11, 19)));
         assert_eq!(num_ways_to_win(30, 200), 9);
     }
 
     #[test]
+    fn ties() {
+        assert_eq!(min_max(4, 4), None);
+        assert_eq!(min_max(4, 3), Some((2, 2)));
+        assert_eq!(min_max(5, 6), None);
+        assert_eq!(min_max(0, 0), None);
+        assert_eq!(min_max(2, 0), Some((1, 1)));
+        // only holding for exactly half of the race beats the record
+        let half = u32::MAX as usize;
+        assert_eq!(min_max(2 * half, half * half - 1), Some((half, half)));
+        assert_eq!(min_max(2 * half, half * half), None);
+        // f32 cannot represent this distance
+        assert_eq!(num_ways_to_win(57726992, 291117211762026), 46561107);
+    }
+
+    #[test]
+    fn brute_force() {
+        for t in 0..80 {
+            for d in 0..=t * t / 4 + 2 {
+                let wins = (0..=t).filter(|s| s * (t - s) > d).collect::<Vec<_>>();
+                let expected = wins.first().map(|&min| (min, *wins.last().unwrap()));
+                assert_eq!(min_max(t, d), expected, "t = {t}, d = {d}");
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(not(input = "day6"), ignore = "res/day6/input.txt is missing")]
     fn part1() {
         let text = std::fs::read_to_string("res/day6/input.txt").unwrap();
-        let mut lines = text.lines();
-        let times = parse_input_line(lines.next().unwrap(), "Time:");
-        let distances = parse_input_line(lines.next().unwrap(), "Distance:");
-        let input = times.zip(distances).collect::<Vec<_>>();
-
-        let solved = input
-            .iter()
-            .map(|(t, d)| solve_for_s(*t as f32, *d as f32))
-            .collect::<Vec<_>>();
-        assert_eq!(
-            solved,
-            &[
-                Some((5.6691, 51.330902)),
-                Some((24.864471, 47.13553)),
-                Some((30.725082, 38.274918)),
-                Some((36.51317, 55.48683)),
-            ]
-        );
+        let input = super::parse_races(&text).unwrap();
 
         let min_max_results = input
             .iter()
@@ -89,27 +216,38 @@ mod tests {
         );
 
         let mut product = 1usize;
-        for (t, d) in input {
+        for &(t, d) in &input {
             product *= num_ways_to_win(t, d);
         }
-        assert_eq!(product, 160816);
+        assert_eq!(product.to_string(), expected(DAY, 1));
     }
 
-    fn parse_input_line<'a>(
-        line: &'a str,
-        prefix: &'static str,
-    ) -> impl Iterator<Item = usize> + 'a {
-        line.strip_prefix(prefix)
-            .unwrap()
-            .split(' ')
-            .filter(|f| !f.is_empty())
-            .map(|f| f.trim().parse::<usize>().unwrap())
+    #[test]
+    fn missing_distances() {
+        let e = Day6::parse("Time:      7  15   30\nDistance:  9  40\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 6, line 2, column 1: expected 3 distances, found \"Distance:  9  40\""
+        );
+
+        let e = Day6::parse("Time: 7 15\nDistance: 99999999999 99999999999\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 6, line 2, column 11: expected a number that fits in usize, \
+             found \"99999999999 99999999999\""
+        );
+
+        let e = super::parse_race("Time: 7 1x\nDistance: 9\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 6, line 1, column 7: expected a number, found \"7 1x\""
+        );
     }
 
     #[test]
+    #[cfg_attr(not(input = "day6"), ignore = "res/day6/input.txt is missing")]
     fn part2() {
-        let t = 57726992;
-        let d = 291117211762026;
-        assert_eq!(num_ways_to_win(t, d), 46561107);
+        let (_, part2) = super::run(Path::new("res/day6/input.txt")).unwrap();
+        assert_eq!(part2.to_string(), expected(DAY, 2));
     }
 }
@@ -1,5 +1,120 @@
 use std::{cmp::Ordering, collections::BTreeMap, path::Path, str::FromStr};
 
+use crate::{
+    error::{ParseError, Result},
+    solution::Solution,
+};
+
+const DAY: usize = 7;
+
+pub struct Day7;
+
+impl Solution for Day7 {
+    type Input = Vec<Bid>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<usize> {
+        ranked_winnings(input, &Rules::camel_cards())
+    }
+
+    fn part2(input: &Self::Input) -> Result<usize> {
+        ranked_winnings(input, &Rules::jokers())
+    }
+}
+
+/// How hands are ranked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// Cards in a hand
+    pub hand_size: usize,
+    /// Cards from the weakest to the strongest
+    pub order: Vec<char>,
+    /// Cards that act like whichever card makes the hand strongest
+    pub wildcards: Vec<char>,
+    pub tie_break: TieBreak,
+}
+
+/// How hands of the same category are ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// The stronger first card wins, then the second, ...
+    Dealt,
+    /// The stronger highest card wins, then the second highest, ...
+    Sorted,
+}
+
+/// Category of a hand followed by its tie-break, so stronger hands are greater
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Strength {
+    /// How often each card appears, most frequent first, with the
+    /// wildcards counted as the most frequent card
+    pub signature: Vec<usize>,
+    /// Ranks of the cards in the order they are compared
+    pub ranks: Vec<usize>,
+}
+
+impl Rules {
+    /// Part 1: five cards, no wildcards
+    pub fn camel_cards() -> Self {
+        Self {
+            hand_size: 5,
+            order: "23456789TJQKA".chars().collect(),
+            wildcards: vec![],
+            tie_break: TieBreak::Dealt,
+        }
+    }
+
+    /// Part 2: `J` is a joker, which is the weakest card on its own
+    pub fn jokers() -> Self {
+        Self {
+            order: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            ..Self::camel_cards()
+        }
+    }
+
+    pub fn rank(&self, card: char) -> Option<usize> {
+        self.order.iter().position(|&c| c == card)
+    }
+
+    /// Strength of a hand like `T55J5`, `None` if it is not a valid hand
+    pub fn strength(&self, hand: &str) -> Option<Strength> {
+        let cards = hand.chars().collect::<Vec<_>>();
+        if cards.len() != self.hand_size {
+            return None;
+        }
+        let mut ranks = cards
+            .iter()
+            .map(|&card| self.rank(card))
+            .collect::<Option<Vec<_>>>()?;
+        let mut counts = vec![0; self.order.len()];
+        let mut wild = 0;
+        for (&card, &rank) in cards.iter().zip(&ranks) {
+            if self.wildcards.contains(&card) {
+                wild += 1;
+            } else {
+                counts[rank] += 1;
+            }
+        }
+        let mut signature = counts.into_iter().filter(|&n| n > 0).collect::<Vec<_>>();
+        signature.sort_unstable_by(|a, b| b.cmp(a));
+        match signature.first_mut() {
+            Some(most) => *most += wild,
+            None if wild > 0 => signature.push(wild),
+            None => {}
+        }
+        if self.tie_break == TieBreak::Sorted {
+            ranks.sort_unstable_by(|a, b| b.cmp(a));
+        }
+        Some(Strength { signature, ranks })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Card {
     Joker,
@@ -27,22 +142,22 @@ impl Card {
         }
     }
 
-    pub fn from_char(c: char) -> Card {
+    pub fn from_char(c: char) -> Option<Card> {
         match c {
-            '2' => Self::C2,
-            '3' => Self::C3,
-            '4' => Self::C4,
-            '5' => Self::C5,
-            '6' => Self::C6,
-            '7' => Self::C7,
-            '8' => Self::C8,
-            '9' => Self::C9,
-            'T' => Self::T,
-            'J' => Self::Jockey,
-            'Q' => Self::Queen,
-            'K' => Self::King,
-            'A' => Self::Ace,
-            _ => panic!("{c}"),
+            '2' => Some(Self::C2),
+            '3' => Some(Self::C3),
+            '4' => Some(Self::C4),
+            '5' => Some(Self::C5),
+            '6' => Some(Self::C6),
+            '7' => Some(Self::C7),
+            '8' => Some(Self::C8),
+            '9' => Some(Self::C9),
+            'T' => Some(Self::T),
+            'J' => Some(Self::Jockey),
+            'Q' => Some(Self::Queen),
+            'K' => Some(Self::King),
+            'A' => Some(Self::Ace),
+            _ => None,
         }
     }
 }
@@ -78,14 +193,13 @@ impl FromStr for Hand {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chars = s.chars();
-        Ok(Self([
-            Card::from_char(chars.next().ok_or(())?),
-            Card::from_char(chars.next().ok_or(())?),
-            Card::from_char(chars.next().ok_or(())?),
-            Card::from_char(chars.next().ok_or(())?),
-            Card::from_char(chars.next().ok_or(())?),
-        ]))
+        let mut chars = s.chars().map(Card::from_char);
+        let mut card = || chars.next().flatten().ok_or(());
+        let hand = Self([card()?, card()?, card()?, card()?, card()?]);
+        match chars.next() {
+            Some(_) => Err(()),
+            None => Ok(hand),
+        }
     }
 }
 
@@ -95,13 +209,15 @@ impl Hand {
     }
 
     pub fn new_part2(s: &str) -> Self {
-        let mut hand = Self::new(s);
+        Self::parse_part2(s).unwrap()
+    }
+
+    pub fn parse_part2(s: &str) -> Option<Self> {
+        let mut hand: Self = s.parse().ok()?;
         for card in &mut hand.0 {
-            if *card == Card::Jockey {
-                *card = Card::Joker;
-            }
+            *card = card.part2();
         }
-        hand
+        Some(hand)
     }
 
     fn hist(&self) -> (BTreeMap<Card, usize>, usize) {
@@ -118,40 +234,14 @@ impl Hand {
     }
 
     pub fn kind(&self) -> Kind {
-        let (hist, _jokers) = self.hist();
-        let mut hist: Vec<_> = hist.iter().map(|(card, count)| (*card, *count)).collect();
-        hist.sort_by_key(|(_, count)| *count);
-        match hist.len() {
-            0 => {
-                /* all jokers */
-                Kind::FiveOfA
-            }
-            1 => Kind::FiveOfA,
-            2 => {
-                // Four of a kind or Full House
-                let (_, count) = hist.last().unwrap();
-                if *count + _jokers == 4 {
-                    Kind::FourOfA
-                } else {
-                    //assert_eq!(*count, 3);
-                    Kind::FullHouse
-                }
-            }
-            3 => {
-                // Two Pair or Three of a kind
-                let (_, count) = hist.last().unwrap();
-                if *count + _jokers == 3 {
-                    Kind::ThreeOfA
-                } else {
-                    //assert_eq!(*count, 2);
-                    Kind::TwoPair
-                }
-            }
-            4 => Kind::OnePair,
-            5 => Kind::HighCard,
-
-            len => panic!("{len}"),
+        let (hist, jokers) = self.hist();
+        let mut signature = hist.into_values().collect::<Vec<_>>();
+        signature.sort_unstable_by(|a, b| b.cmp(a));
+        match signature.first_mut() {
+            Some(most) => *most += jokers,
+            None => signature.push(jokers),
         }
+        Kind::of(&signature).expect("a hand has five cards")
     }
 }
 
@@ -166,33 +256,102 @@ pub enum Kind {
     FiveOfA,
 }
 
-pub fn run(path: &Path, parser: impl Fn(&str) -> Hand) -> usize {
-    let text = std::fs::read_to_string(path).unwrap();
-    let mut bids: Vec<_> = text
-        .lines()
-        .filter_map(|s| s.split_once(' '))
-        .map(|(a, b)| (parser(a), b.parse::<usize>().unwrap()))
-        .collect();
-    bids.sort_by_key(|(card, _bid)| *card);
-    bids.into_iter()
+impl Kind {
+    /// Kind of a five card hand from its [`Strength::signature`]
+    pub fn of(signature: &[usize]) -> Option<Self> {
+        match signature {
+            [5] => Some(Kind::FiveOfA),
+            [4, 1] => Some(Kind::FourOfA),
+            [3, 2] => Some(Kind::FullHouse),
+            [3, 1, 1] => Some(Kind::ThreeOfA),
+            [2, 2, 1] => Some(Kind::TwoPair),
+            [2, 1, 1, 1] => Some(Kind::OnePair),
+            [1, 1, 1, 1, 1] => Some(Kind::HighCard),
+            _ => None,
+        }
+    }
+}
+
+/// A hand and its bid; the hand is only checked by the [`Rules`] it is ranked with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bid {
+    pub hand: String,
+    pub bid: usize,
+    /// 1-based line of the bid, for errors about the hand
+    pub line: usize,
+}
+
+pub fn parse(text: &str) -> Result<Vec<Bid>> {
+    let err = |part, expected| ParseError::at(DAY, text, part, expected);
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            let (hand, bid) = line
+                .split_once(' ')
+                .ok_or_else(|| err(line, "a hand and a bid"))?;
+            let bid = bid.parse().map_err(|_| err(bid, "a bid"))?;
+            Ok(Bid {
+                hand: hand.to_owned(),
+                bid,
+                line: i + 1,
+            })
+        })
+        .collect()
+}
+
+pub fn run(path: &Path, parser: impl Fn(&str) -> Option<Hand>) -> Result<usize> {
+    let text = std::fs::read_to_string(path)?;
+    total_winnings(&parse(&text)?, parser)
+}
+
+pub fn total_winnings(bids: &[Bid], parser: impl Fn(&str) -> Option<Hand>) -> Result<usize> {
+    winnings(bids, "a hand of five cards", parser)
+}
+
+pub fn ranked_winnings(bids: &[Bid], rules: &Rules) -> Result<usize> {
+    let expected = format!("a hand of {} cards", rules.hand_size);
+    winnings(bids, &expected, |hand| rules.strength(hand))
+}
+
+/// Sum of the bids times their rank, with hands ordered by their key
+fn winnings<K: Ord>(
+    bids: &[Bid],
+    expected: &str,
+    key: impl Fn(&str) -> Option<K>,
+) -> Result<usize> {
+    let mut bids = bids
+        .iter()
+        .map(|Bid { hand, bid, line }| {
+            let key = key(hand)
+                .ok_or_else(|| ParseError::at(DAY, hand, hand, expected).on_line(*line))?;
+            Ok((key, *bid))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    bids.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(bids
+        .into_iter()
         .enumerate()
         .map(|(i, (_, bid))| (i + 1) * bid)
-        .sum()
+        .sum())
 }
 
-pub fn part1(path: &Path) -> usize {
-    run(path, Hand::new)
+pub fn part1(path: &Path) -> Result<usize> {
+    run(path, |s| s.parse().ok())
 }
 
-pub fn part2(path: &Path) -> usize {
-    run(path, Hand::new_part2)
+pub fn part2(path: &Path) -> Result<usize> {
+    run(path, Hand::parse_part2)
 }
 
 #[cfg(test)]
 mod tests {
     use std::path::Path;
 
-    use super::{Hand, Kind};
+    use super::{Hand, Kind, Rules, TieBreak, DAY};
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day7/example.txt");
 
     #[test]
     fn kinds() {
@@ -208,14 +367,122 @@ mod tests {
     }
 
     #[test]
+    fn signatures() {
+        let jokers = Rules::jokers();
+        let signature = |hand| jokers.strength(hand).unwrap().signature;
+        assert_eq!(signature("QJJQ2"), [4, 1]);
+        assert_eq!(signature("JJJJJ"), [5]);
+        assert_eq!(signature("T55J5"), [4, 1]);
+        assert!(jokers.strength("JKKK2") < jokers.strength("QQQQ2"));
+        assert_eq!(jokers.strength("AAAA"), None);
+
+        // three card poker-like hands, where the highest card breaks ties
+        let rules = Rules {
+            hand_size: 3,
+            order: "23456789TJQKA".chars().collect(),
+            wildcards: vec!['2'],
+            tie_break: TieBreak::Sorted,
+        };
+        assert!(rules.strength("A34") < rules.strength("35A"));
+        assert!(rules.strength("A52") > rules.strength("KKQ"));
+        assert_eq!(rules.strength("222").unwrap().signature, [3]);
+    }
+
+    /// The category of every hand is that of the best hand its wildcards can
+    /// stand for, and agrees with [`Hand::kind`]
+    #[test]
+    fn all_hands() {
+        for (rules, parse) in [
+            (Rules::camel_cards(), Hand::new as fn(&str) -> Hand),
+            (Rules::jokers(), Hand::new_part2),
+        ] {
+            let n = rules.order.len();
+            let plain = rules.order.iter().filter(|c| !rules.wildcards.contains(c));
+            let plain = plain.copied().collect::<Vec<_>>();
+            let no_wildcards = Rules {
+                wildcards: vec![],
+                ..rules.clone()
+            };
+            for i in 0..n.pow(5) {
+                let hand = (0..5)
+                    .map(|k| rules.order[i / n.pow(k) % n])
+                    .collect::<String>();
+                let signature = rules.strength(&hand).unwrap().signature;
+                assert_eq!(Kind::of(&signature), Some(parse(&hand).kind()), "{hand}");
+
+                // Standing for the cards in the hand or for other, distinct
+                // cards covers every outcome
+                let wild = hand
+                    .char_indices()
+                    .filter(|(_, c)| rules.wildcards.contains(c))
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>();
+                let (mut choices, unused): (Vec<char>, Vec<char>) =
+                    plain.iter().partition(|&&c| hand.contains(c));
+                choices.extend(unused.into_iter().take(wild.len()));
+                let m = choices.len();
+                let best = (0..m.pow(wild.len() as u32))
+                    .map(|j| {
+                        let mut cards = hand.chars().collect::<Vec<_>>();
+                        for (k, &at) in wild.iter().enumerate() {
+                            cards[at] = choices[j / m.pow(k as u32) % m];
+                        }
+                        let hand = cards.into_iter().collect::<String>();
+                        no_wildcards.strength(&hand).unwrap().signature
+                    })
+                    .max()
+                    .unwrap();
+                assert_eq!(signature, best, "{hand}");
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_hand() {
+        let bids = super::parse("32T3K 765\nT55X5 684\n").unwrap();
+        let e = super::total_winnings(&bids, |s| s.parse().ok()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 7, line 2, column 1: expected a hand of five cards, found \"T55X5\""
+        );
+        assert_eq!("AAAAAA".parse::<Hand>(), Err(()));
+
+        let e = super::ranked_winnings(&bids[1..], &Rules::jokers()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 7, line 2, column 1: expected a hand of 5 cards, found \"T55X5\""
+        );
+    }
+
+    #[test]
+    fn example1() {
+        let bids = super::parse(EXAMPLE).unwrap();
+        let winnings = super::total_winnings(&bids, |s| s.parse().ok()).unwrap();
+        assert_eq!(winnings, 6440);
+        let winnings = super::ranked_winnings(&bids, &Rules::camel_cards()).unwrap();
+        assert_eq!(winnings, 6440);
+    }
+
+    #[test]
+    #[cfg_attr(not(input = "day7"), ignore = "res/day7/input.txt is missing")]
     fn part1() {
-        assert_eq!(super::part1(Path::new("res/day7/example.txt")), 6440);
-        assert_eq!(super::part1(Path::new("res/day7/input.txt")), 251545216);
+        let part1 = super::part1(Path::new("res/day7/input.txt")).unwrap();
+        assert_eq!(part1.to_string(), expected(DAY, 1));
+    }
+
+    #[test]
+    fn example2() {
+        let bids = super::parse(EXAMPLE).unwrap();
+        let winnings = super::total_winnings(&bids, Hand::parse_part2).unwrap();
+        assert_eq!(winnings, 5905);
+        let winnings = super::ranked_winnings(&bids, &Rules::jokers()).unwrap();
+        assert_eq!(winnings, 5905);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day7"), ignore = "res/day7/input.txt is missing")]
     fn part2() {
-        assert_eq!(super::part2(Path::new("res/day7/example.txt")), 5905);
-        assert_eq!(super::part2(Path::new("res/day7/input.txt")), 250384185);
+        let part2 = super::part2(Path::new("res/day7/input.txt")).unwrap();
+        assert_eq!(part2.to_string(), expected(DAY, 2));
     }
 }
@@ -1,57 +1,52 @@
 use std::{
     collections::{BTreeMap, BTreeSet},
-    fmt,
     path::Path,
-    str::FromStr,
 };
 
-use num::integer::lcm;
+use log::debug;
+use num::Integer;
 use regex::Regex;
 
-pub enum Dir {
-    Left,
-    Right,
-}
+use crate::{
+    error::{Error, ParseError, Result},
+    graph::{Edge, Graph, Node},
+    solution::Solution,
+};
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct StateID(u32);
+const DAY: usize = 8;
 
-impl StateID {
-    pub fn as_str(&self) -> &str {
-        unsafe {
-            std::str::from_utf8_unchecked(std::slice::from_raw_parts(
-                &self.0 as *const u32 as *const u8,
-                3,
-            ))
-        }
-    }
+pub struct Day8;
+
+impl Solution for Day8 {
+    type Input = Data;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-    pub fn is_end(&self) -> bool {
-        const Z: u32 = u32::from_ne_bytes([0, 0, b'Z', 0]);
-        const MASK: u32 = u32::from_ne_bytes([0, 0, 0xFF, 0]);
-        self.0 & MASK == Z
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse(text)
     }
-}
 
-impl FromStr for StateID {
-    type Err = ();
+    fn part1(input: &Self::Input) -> Result<usize> {
+        steps(input)
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match *s.as_bytes() {
-            [a, b, c] if a.is_ascii() && b.is_ascii() && c.is_ascii() => {
-                Ok(Self(u32::from_ne_bytes([a, b, c, 0])))
-            }
-            _ => Err(()),
-        }
+    fn part2(input: &Self::Input) -> Result<usize> {
+        ghost_steps(input, ghost_start, ghost_end)?.ok_or(Error::unsolvable(
+            DAY,
+            "the ghosts never are on end nodes at once",
+        ))
     }
 }
 
-impl fmt::Debug for StateID {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.as_str().fmt(f)
-    }
+pub enum Dir {
+    Left,
+    Right,
 }
 
+/// A node, as an index into the names of [`Data`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StateID(u32);
+
 #[derive(Debug, Copy, Clone)]
 pub struct State {
     pub left: StateID,
@@ -61,249 +56,460 @@ pub struct State {
 pub struct Data {
     steps: Vec<Dir>,
     transitions: BTreeMap<StateID, State>,
-    start: BTreeSet<StateID>,
-    end: BTreeSet<StateID>,
+    /// Names of the nodes by their ID
+    names: Vec<String>,
+    ids: BTreeMap<String, StateID>,
 }
 
-pub fn run(path: &Path) -> Data {
-    let _text = std::fs::read_to_string(path).unwrap();
-    let mut lines = _text.lines();
-
-    let steps = lines
-        .next()
-        .unwrap()
-        .chars()
-        .map(|c| match c {
-            'L' => Dir::Left,
-            'R' => Dir::Right,
-            _ => panic!(),
-        })
-        .collect::<Vec<_>>();
-    lines.next();
+impl Data {
+    /// ID of the node `name`, adding it if it is new
+    fn intern(&mut self, name: &str) -> StateID {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = StateID(self.names.len() as u32);
+        self.names.push(name.to_owned());
+        self.ids.insert(name.to_owned(), id);
+        id
+    }
 
-    let mut start = BTreeSet::<StateID>::new();
-    let mut end = BTreeSet::<StateID>::new();
-    let regex = Regex::new(r"([A-Z0-9]{3}) = \(([A-Z0-9]{3}), ([A-Z0-9]{3})\)").unwrap();
-    let transitions = lines
-        .map(|l| {
-            let cap = regex.captures(l).unwrap();
-            let name = cap.get(1).unwrap().as_str();
-            let name_id = name.parse().unwrap();
-            if name.ends_with('A') {
-                start.insert(name_id);
-            } else if name.ends_with('Z') {
-                end.insert(name_id);
-            }
-            let left = cap.get(2).unwrap().as_str();
-            let right = cap.get(3).unwrap().as_str();
-            (
-                name_id,
-                State {
-                    left: left.parse().unwrap(),
-                    right: right.parse().unwrap(),
-                },
-            )
-        })
-        .collect::<BTreeMap<_, _>>();
-    Data {
-        steps,
-        transitions,
-        start,
-        end,
+    pub fn id(&self, name: &str) -> Option<StateID> {
+        self.ids.get(name).copied()
     }
-}
 
-pub fn part1(path: &Path) -> usize {
-    let data = run(path);
-    let end = "ZZZ".parse().unwrap();
-    let mut state = "AAA".parse().unwrap();
-    let mut dir = data.steps.iter().cycle();
-    let mut count = 0;
-    while state != end {
-        let s = data.transitions.get(&state).unwrap();
-        state = match dir.next().unwrap() {
+    pub fn name(&self, id: StateID) -> &str {
+        &self.names[id.0 as usize]
+    }
+
+    /// Nodes with a line of their own, whose names match `predicate`
+    pub fn nodes(&self, predicate: impl Fn(&str) -> bool) -> Vec<StateID> {
+        self.transitions
+            .keys()
+            .copied()
+            .filter(|&id| predicate(self.name(id)))
+            .collect()
+    }
+
+    fn next(&self, state: StateID, dir: &Dir) -> Result<StateID> {
+        let s = self
+            .transitions
+            .get(&state)
+            .ok_or(Error::unsolvable(DAY, "path leads to an unknown node"))?;
+        Ok(match dir {
             Dir::Left => s.left,
             Dir::Right => s.right,
-        };
-        count += 1;
+        })
     }
-    count
 }
 
-/*#[derive(Debug)]
-pub struct CycleInfo {
-    count: usize,
-    ends: BTreeSet<usize>,
-    end_syms: BTreeSet<usize>,
-}*/
+/// The network, with the nodes matching `is_start` and `is_end` highlighted
+pub fn graph(data: &Data, is_start: impl Fn(&str) -> bool, is_end: impl Fn(&str) -> bool) -> Graph {
+    let edge = |to, label: &str| Edge {
+        to: data.name(to).to_owned(),
+        label: Some(label.to_owned()),
+    };
+    let nodes = data
+        .transitions
+        .iter()
+        .map(|(&id, state)| {
+            let name = data.name(id);
+            let mut node = Node::new(name);
+            if is_start(name) {
+                node.kind = Some("start");
+                node.attrs = vec![("style", "filled"), ("fillcolor", "palegreen")];
+            } else if is_end(name) {
+                node.kind = Some("end");
+                node.attrs = vec![("style", "filled"), ("fillcolor", "salmon")];
+            }
+            node.edges = if state.left == state.right {
+                vec![edge(state.left, "L/R")]
+            } else {
+                vec![edge(state.left, "L"), edge(state.right, "R")]
+            };
+            node
+        })
+        .collect();
+    Graph { nodes }
+}
 
-fn c(s: &str) -> StateID {
-    s.parse().unwrap()
+/// Start nodes of the ghosts
+pub fn ghost_start(name: &str) -> bool {
+    name.ends_with('A')
 }
 
-pub struct StateIter<'a> {
-    next: StateID,
-    t: &'a BTreeMap<StateID, StateID>,
+/// End nodes of the ghosts
+pub fn ghost_end(name: &str) -> bool {
+    name.ends_with('Z')
 }
 
-impl<'a> StateIter<'a> {
-    pub fn new(t: &'a BTreeMap<StateID, StateID>, start: StateID) -> Self {
-        Self { t, next: start }
-    }
+pub fn run(path: &Path) -> Result<Data> {
+    let text = std::fs::read_to_string(path)?;
+    parse(&text)
 }
 
-impl Iterator for StateIter<'_> {
-    type Item = StateID;
+pub fn parse(text: &str) -> Result<Data> {
+    let mut lines = text.lines();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let then = *self.t.get(&self.next)?;
-        self.next = then;
-        Some(then)
+    let step_line = lines.next().unwrap_or_default();
+    let steps = step_line
+        .char_indices()
+        .map(|(i, c)| match c {
+            'L' => Ok(Dir::Left),
+            'R' => Ok(Dir::Right),
+            _ => Err(ParseError::at(DAY, text, &step_line[i..], "'L' or 'R'")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if steps.is_empty() {
+        return Err(ParseError::at(DAY, text, step_line, "a list of steps").into());
     }
-}
+    lines.next();
 
-pub fn part2_special_case(path: &Path) -> usize {
-    let data = run(path);
+    let mut data = Data {
+        steps,
+        transitions: BTreeMap::new(),
+        names: vec![],
+        ids: BTreeMap::new(),
+    };
+    let regex = Regex::new(r"^(\w+) = \((\w+), (\w+)\)$").unwrap();
+    for l in lines.filter(|l| !l.is_empty()) {
+        let cap = regex
+            .captures(l)
+            .ok_or_else(|| ParseError::at(DAY, text, l, "a node \"AAA = (BBB, CCC)\""))?;
+        let [name, left, right] = [1, 2, 3].map(|i| data.intern(&cap[i]));
+        data.transitions.insert(name, State { left, right });
+    }
+    Ok(data)
+}
 
-    let steps_per_cycle = data.steps.len();
-    assert_eq!(steps_per_cycle, 281);
+pub fn part1(path: &Path) -> Result<usize> {
+    steps(&run(path)?)
+}
 
-    let starts_check: [StateID; 6] = [c("AAA"), c("NFA"), c("LJA"), c("PLA"), c("KTA"), c("JXA")];
-    assert_eq!(data.start, BTreeSet::from(starts_check));
+/// Steps from `AAA` to `ZZZ`
+pub fn steps(data: &Data) -> Result<usize> {
+    steps_between(data, "AAA", |name| name == "ZZZ")
+}
 
-    let end_check: [StateID; 6] = [c("HBZ"), c("BGZ"), c("RGZ"), c("DLZ"), c("NTZ"), c("ZZZ")];
-    assert_eq!(data.end, BTreeSet::from(end_check));
+/// Steps from the node `from` to the first node matching `to`
+pub fn steps_between(data: &Data, from: &str, to: impl Fn(&str) -> bool) -> Result<usize> {
+    let mut state = data
+        .id(from)
+        .ok_or_else(|| Error::unsolvable(DAY, format!("there is no node {from}")))?;
+    let mut dir = data.steps.iter().cycle();
+    let mut count = 0;
+    // after this many steps, a node is visited twice at the same step
+    let limit = data.names.len() * data.steps.len();
+    while !to(data.name(state)) {
+        if count > limit {
+            return Err(Error::unsolvable(DAY, "the path never reaches the end"));
+        }
+        state = data.next(state, dir.next().unwrap())?;
+        count += 1;
+    }
+    Ok(count)
+}
 
-    let mut ends = BTreeMap::<StateID, Vec<(usize, StateID)>>::new();
-    let mut cycle_state = BTreeMap::<StateID, StateID>::new();
+/// When a ghost walking from one start node is on an end node
+///
+/// The walk is in a cycle once it is back on a node at the same position in
+/// the list of steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walk {
+    /// Times of the hits before the cycle
+    pub prefix_hits: Vec<usize>,
+    /// Time the cycle starts
+    pub cycle_start: usize,
+    pub cycle_len: usize,
+    /// Times of the hits in the first pass of the cycle, which repeat every
+    /// `cycle_len` steps
+    pub cycle_hits: Vec<usize>,
+}
 
-    for s in data.transitions.keys() {
-        let start_sym = *s;
-        let mut state = start_sym;
+impl Walk {
+    pub fn is_hit(&self, time: usize) -> bool {
+        if time < self.cycle_start {
+            self.prefix_hits.contains(&time)
+        } else {
+            let time = self.cycle_start + (time - self.cycle_start) % self.cycle_len;
+            self.cycle_hits.contains(&time)
+        }
+    }
+}
 
-        for (index, step) in data.steps.iter().enumerate() {
-            let transition = data.transitions.get(&state).unwrap();
-            state = match *step {
-                Dir::Left => transition.left,
-                Dir::Right => transition.right,
-            };
-            if state.is_end() {
-                ends.entry(start_sym).or_default().push((index, state));
-            }
+/// Follows the steps from `start` until they repeat, noting the nodes
+/// matching `is_end`
+pub fn walk(data: &Data, start: StateID, is_end: impl Fn(&str) -> bool) -> Result<Walk> {
+    let mut seen = BTreeMap::<(StateID, usize), usize>::new();
+    let mut hits = vec![];
+    let mut state = start;
+    for time in 0.. {
+        let index = time % data.steps.len();
+        if let Some(&cycle_start) = seen.get(&(state, index)) {
+            let cycle_hits = hits.split_off(hits.partition_point(|&t| t < cycle_start));
+            return Ok(Walk {
+                prefix_hits: hits,
+                cycle_start,
+                cycle_len: time - cycle_start,
+                cycle_hits,
+            });
         }
+        seen.insert((state, index), time);
+        if is_end(data.name(state)) {
+            hits.push(time);
+        }
+        state = data.next(state, &data.steps[index])?;
+    }
+    unreachable!("the states are finite")
+}
 
-        cycle_state.insert(start_sym, state);
+pub fn part2(path: &Path) -> Result<Option<usize>> {
+    ghost_steps(&run(path)?, ghost_start, ghost_end)
+}
+
+/// Steps until the ghosts from all nodes matching `is_start` are on nodes
+/// matching `is_end` at once
+///
+/// Before every walk is in its cycle, the times are checked one by one.
+/// Afterwards, each walk is on an end node for some remainders modulo its
+/// cycle length, which are combined with the Chinese remainder theorem.
+/// `None` if there is no such time, or no start node.
+pub fn ghost_steps(
+    data: &Data,
+    is_start: impl Fn(&str) -> bool,
+    is_end: impl Fn(&str) -> bool,
+) -> Result<Option<usize>> {
+    let starts = data.nodes(is_start);
+    if starts.is_empty() {
+        return Ok(None);
+    }
+    let walks = starts
+        .into_iter()
+        .map(|start| walk(data, start, &is_end))
+        .collect::<Result<Vec<_>>>()?;
+    let cycles_start = walks.iter().map(|w| w.cycle_start).max().unwrap();
+    if let Some(time) = (0..cycles_start).find(|&t| walks.iter().all(|w| w.is_hit(t))) {
+        return Ok(Some(time));
     }
 
-    // Note how all of them are at index 280, so one full cycle of the input step pattern
-    let ends_check = BTreeMap::from([
-        (c("VXB"), vec![(280, c("NTZ"))]),
-        (c("VBD"), vec![(280, c("DLZ"))]),
-        (c("JLD"), vec![(280, c("BGZ"))]),
-        (c("HNH"), vec![(280, c("ZZZ"))]),
-        (c("CJJ"), vec![(280, c("BGZ"))]),
-        (c("CDL"), vec![(280, c("HBZ"))]),
-        (c("RJP"), vec![(280, c("NTZ"))]),
-        (c("KLQ"), vec![(280, c("HBZ"))]),
-        (c("CCR"), vec![(280, c("ZZZ"))]),
-        (c("LCR"), vec![(280, c("RGZ"))]),
-        (c("SST"), vec![(280, c("DLZ"))]),
-        (c("CPX"), vec![(280, c("RGZ"))]),
-    ]);
-
-    assert_eq!(ends.len(), 12);
-    assert_eq!(ends, ends_check);
-
-    let states = data
-        .start
-        .iter()
-        .copied()
-        .map(|s| (s, map_state(&cycle_state, s)))
-        .collect::<BTreeMap<_, _>>();
-    assert_eq!(
-        states,
-        BTreeMap::from([
-            (c("AAA"), (59, c("ZZZ"))),
-            (c("NFA"), (43, c("HBZ"))),
-            (c("LJA"), (79, c("BGZ"))),
-            (c("PLA"), (71, c("RGZ"))),
-            (c("KTA"), (53, c("DLZ"))),
-            (c("JXA"), (61, c("NTZ"))),
-        ])
-    );
-
-    let end_states = data
-        .end
-        .iter()
-        .copied()
-        .map(|s| (s, map_state(&cycle_state, s)))
-        .collect::<BTreeMap<_, _>>();
-
-    // Two notes: they all point into each other, AND the cycle length is the same as for
-    // for the basic one.
-    assert_eq!(
-        end_states,
-        BTreeMap::from([
-            (c("HBZ"), (43, c("HBZ"))),
-            (c("BGZ"), (79, c("BGZ"))),
-            (c("RGZ"), (71, c("RGZ"))),
-            (c("DLZ"), (53, c("DLZ"))),
-            (c("NTZ"), (61, c("NTZ"))),
-            (c("ZZZ"), (59, c("ZZZ"))),
-        ])
-    );
-
-    let mut s = end_states
-        .values()
-        .map(|(cycle_len, _s)| *cycle_len * steps_per_cycle)
-        .collect::<Vec<_>>();
-    s.sort();
-    assert_eq!(s, vec![12083, 14893, 16579, 17141, 19951, 22199]);
-    s.into_iter().fold(1, lcm)
+    let mut congruences = BTreeSet::from([(0, 1)]);
+    for walk in &walks {
+        let len = walk.cycle_len as u128;
+        congruences = congruences
+            .into_iter()
+            .flat_map(|c| walk.cycle_hits.iter().map(move |&t| (c, t)))
+            .filter_map(|(c, t)| crt(c, (t as u128 % len, len)))
+            .collect();
+    }
+    debug!("{} combinations of cycle hits", congruences.len());
+    let start = cycles_start as u128;
+    Ok(congruences
+        .into_iter()
+        .filter_map(|(r, m)| {
+            // the first time from `start` on with the remainder `r`
+            let time = start + (r + m - start % m) % m;
+            usize::try_from(time).ok()
+        })
+        .min())
 }
 
-fn map_state(cycle_state: &BTreeMap<StateID, StateID>, s: StateID) -> (usize, StateID) {
-    StateIter::new(cycle_state, s)
-        .enumerate()
-        .find(|(_, s)| s.is_end())
-        .map(|(i, s)| (i + 1, s))
-        .unwrap()
+/// Combines x = a (mod m) and x = b (mod n) into one congruence
+///
+/// `None` if they have no common solution, or its modulus does not fit.
+fn crt((a, m): (u128, u128), (b, n): (u128, u128)) -> Option<(u128, u128)> {
+    let gcd = m.gcd(&n);
+    let diff = (b as i128).checked_sub(a as i128)?;
+    if diff % gcd as i128 != 0 {
+        return None;
+    }
+    let lcm = (m / gcd).checked_mul(n)?;
+    // a + m * k = b (mod n)  <=>  (m / g) * k = (b - a) / g  (mod n / g)
+    let n_g = (n / gcd) as i128;
+    let inverse = ((m / gcd) as i128).extended_gcd(&n_g).x;
+    let k = (diff / gcd as i128 % n_g)
+        .checked_mul(inverse)?
+        .rem_euclid(n_g);
+    let x = (a + m.checked_mul(k as u128)?) % lcm;
+    Some((x, lcm))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{c, StateID};
+    use super::{ghost_end, ghost_start, Walk, DAY};
+    use crate::answers::expected;
     use std::path::Path;
 
+    const EXAMPLE: &str = include_str!("../res/day8/example.txt");
+    const EXAMPLE2: &str = include_str!("../res/day8/example2.txt");
+    const EXAMPLE3: &str = include_str!("../res/day8/example3.txt");
+
     #[test]
     fn example1() {
-        let c1 = super::part1(Path::new("res/day8/example.txt"));
+        let c1 = super::steps(&super::parse(EXAMPLE).unwrap()).unwrap();
         assert_eq!(c1, 2);
     }
 
     #[test]
     fn example1b() {
-        let c1 = super::part1(Path::new("res/day8/example2.txt"));
+        let c1 = super::steps(&super::parse(EXAMPLE2).unwrap()).unwrap();
         assert_eq!(c1, 6);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day8"), ignore = "res/day8/input.txt is missing")]
     fn part1() {
-        let c1 = super::part1(Path::new("res/day8/input.txt"));
-        assert_eq!(c1, 16579);
+        let c1 = super::part1(Path::new("res/day8/input.txt")).unwrap();
+        assert_eq!(c1.to_string(), expected(DAY, 1));
+    }
+
+    #[test]
+    fn invalid_step() {
+        let e = super::parse("LRX\n\nAAA = (BBB, CCC)\n").err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "day 8, line 1, column 3: expected 'L' or 'R', found \"X\""
+        );
     }
 
     #[test]
-    fn state_id() {
-        assert_eq!("ABC", "ABC".parse::<StateID>().unwrap().as_str());
-        assert!(!c("ABC").is_end());
-        assert!(!c("ZBC").is_end());
-        assert!(c("ABZ").is_end());
+    fn graph() {
+        let data = super::parse(EXAMPLE3).unwrap();
+        let graph = super::graph(&data, ghost_start, ghost_end);
+        assert_eq!(graph.nodes.len(), 8);
+        let node = &graph.nodes[0];
+        assert_eq!((node.id.as_str(), node.kind), ("11A", Some("start")));
+        let edges = node
+            .edges
+            .iter()
+            .map(|e| (e.to.as_str(), e.label.as_deref()));
+        assert_eq!(
+            edges.collect::<Vec<_>>(),
+            [("11B", Some("L")), ("XXX", Some("R"))]
+        );
+        let dot = graph.to_dot("day8");
+        assert!(dot.contains("    \"22Z\" [style=\"filled\", fillcolor=\"salmon\"];\n"));
+        assert!(dot.contains("    \"22B\" -> \"22C\" [label=\"L/R\"];\n"));
     }
 
     #[test]
+    fn names() {
+        let data =
+            super::parse("LR\n\nstart = (A, end)\nA = (start, end)\nend = (end, end)\n").unwrap();
+        let start = data.id("start").unwrap();
+        assert_eq!(data.name(start), "start");
+        assert_eq!(data.id("A").map(|id| data.name(id)), Some("A"));
+        assert_eq!(data.id("B"), None);
+        assert_eq!(
+            super::steps_between(&data, "start", |n| n == "end").unwrap(),
+            2
+        );
+        let e = super::steps(&data).unwrap_err();
+        assert_eq!(e.to_string(), "day 8: there is no node AAA");
+        let e = super::steps_between(&data, "end", |n| n == "A").unwrap_err();
+        assert_eq!(e.to_string(), "day 8: the path never reaches the end");
+
+        let is_start = |n: &str| n.len() > 3;
+        let ghosts = super::ghost_steps(&data, is_start, |n| n.starts_with('e'));
+        assert_eq!(ghosts.unwrap(), Some(2));
+        assert_eq!(data.nodes(is_start), [start]);
+        assert!(super::parse("L\n\nAAA = (BBB, C-C)\n").is_err());
+    }
+
+    #[test]
+    #[cfg_attr(not(input = "day8"), ignore = "res/day8/input.txt is missing")]
     fn part2() {
-        let steps = super::part2_special_case(Path::new("res/day8/input.txt"));
-        assert_eq!(steps, 12927600769609);
+        let steps = super::part2(Path::new("res/day8/input.txt")).unwrap();
+        assert_eq!(steps.unwrap().to_string(), expected(DAY, 2));
+    }
+
+    #[test]
+    fn example2() {
+        let data = super::parse(EXAMPLE3).unwrap();
+        let walk = super::walk(&data, data.id("22A").unwrap(), ghost_end).unwrap();
+        assert_eq!(
+            walk,
+            Walk {
+                prefix_hits: vec![],
+                cycle_start: 1,
+                cycle_len: 6,
+                cycle_hits: vec![3, 6],
+            }
+        );
+        assert_eq!(
+            super::ghost_steps(&data, ghost_start, ghost_end).unwrap(),
+            Some(6)
+        );
+    }
+
+    #[test]
+    fn ghost_cycles() {
+        let ghosts = |nodes: &str| {
+            let data = super::parse(&format!("L\n\n{nodes}")).unwrap();
+            super::ghost_steps(&data, ghost_start, ghost_end).unwrap()
+        };
+        // cycles of 2 and 4 with hits at odd and even times never meet
+        let never = "AAA = (AAZ, AAZ)\nAAZ = (AAA, AAA)\nBBA = (BB1, BB1)\n\
+                     BB1 = (BBZ, BBZ)\nBBZ = (BB3, BB3)\nBB3 = (BBA, BBA)\n";
+        assert_eq!(ghosts(never), None);
+        // cycles of 4 and 6 with two hits each meet at 6
+        let twice = "AAA = (AA1, AA1)\nAA1 = (AAZ, AAZ)\nAAZ = (AA3, AA3)\n\
+                     AA3 = (A1Z, A1Z)\nA1Z = (AA1, AA1)\n\
+                     BBA = (BB1, BB1)\nBB1 = (BB2, BB2)\nBB2 = (BB3, BB3)\n\
+                     BB3 = (BB4, BB4)\nBB4 = (BBZ, BBZ)\nBBZ = (B1Z, B1Z)\n\
+                     B1Z = (BB1, BB1)\n";
+        assert_eq!(ghosts(twice), Some(6));
+        // a hit before the cycle
+        let prefix = "AAA = (AAZ, AAZ)\nAAZ = (AA1, AA1)\nAA1 = (AA1, AA1)\n";
+        assert_eq!(ghosts(prefix), Some(1));
+    }
+
+    #[test]
+    fn brute_force() {
+        let mut seed = 0x2545f491u32;
+        let mut random = |n: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as usize % n
+        };
+        for _ in 0..500 {
+            let n = 2 + random(7);
+            let names = (0..n)
+                .map(|i| format!("N{i}{}", ['A', 'Z', 'B'][random(3)]))
+                .collect::<Vec<_>>();
+            let steps = (0..1 + random(3))
+                .map(|_| ['L', 'R'][random(2)])
+                .collect::<String>();
+            let nodes = names
+                .iter()
+                .map(|name| format!("{name} = ({}, {})\n", names[random(n)], names[random(n)]))
+                .collect::<String>();
+            let data = super::parse(&format!("{steps}\n\n{nodes}")).unwrap();
+            let starts = data.nodes(ghost_start);
+            let walks = starts
+                .iter()
+                .map(|&start| super::walk(&data, start, ghost_end).unwrap())
+                .collect::<Vec<_>>();
+            // the walks meet within the product of their cycle lengths, if ever
+            let bound = walks
+                .iter()
+                .map(|w| w.cycle_start + w.cycle_len)
+                .product::<usize>();
+            let expected = (0..=bound).find(|&t| walks.iter().all(|w| w.is_hit(t)));
+            let expected = expected.filter(|_| !walks.is_empty());
+            let ghosts = super::ghost_steps(&data, ghost_start, ghost_end).unwrap();
+            assert_eq!(ghosts, expected, "{steps}\n{nodes}");
+
+            for (walk, &start) in walks.iter().zip(&starts) {
+                let mut state = start;
+                for t in 0..bound.min(200) {
+                    assert_eq!(walk.is_hit(t), ghost_end(data.name(state)));
+                    let s = data.transitions[&state];
+                    state = match steps.as_bytes()[t % steps.len()] {
+                        b'L' => s.left,
+                        _ => s.right,
+                    };
+                }
+            }
+        }
     }
 }
@@ -1,74 +1,248 @@
-use std::{mem, path::Path};
+use std::path::Path;
 
-pub fn run(path: &Path) -> Vec<Vec<isize>> {
-    std::fs::read_to_string(path).map(parse_sequences).unwrap()
+use num::{BigInt, BigRational, One, Zero};
+
+use crate::{
+    error::{Error, ParseError, Result},
+    solution::Solution,
+};
+
+const DAY: usize = 9;
+
+pub struct Day9;
+
+impl Solution for Day9 {
+    type Input = Vec<Vec<isize>>;
+    type Answer1 = isize;
+    type Answer2 = isize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        parse_sequences(text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<isize> {
+        sum(predict_lists(input, predict_next)?)
+    }
+
+    fn part2(input: &Self::Input) -> Result<isize> {
+        sum(predict_lists(input, predict_prev)?)
+    }
 }
 
-fn parse_sequences(text: String) -> Vec<Vec<isize>> {
+pub fn run(path: &Path) -> Result<Vec<Vec<isize>>> {
+    let text = std::fs::read_to_string(path)?;
+    parse_sequences(&text)
+}
+
+pub fn parse_sequences(text: &str) -> Result<Vec<Vec<isize>>> {
     text.lines()
         .filter(|&line| !line.is_empty())
-        .map(|line| line.split(' ').map(|i| i.parse().unwrap()).collect())
+        .map(|line| {
+            line.split(' ')
+                .map(|i| {
+                    i.parse()
+                        .map_err(|_| ParseError::at(DAY, text, i, "a number").into())
+                })
+                .collect()
+        })
         .collect()
 }
 
-pub fn predict_lists(lists: &mut [Vec<isize>], with: impl Fn(&mut [isize]) -> isize) -> Vec<isize> {
-    lists.iter_mut().map(Vec::as_mut_slice).map(with).collect()
+fn sum(predictions: Vec<isize>) -> Result<isize> {
+    predictions
+        .into_iter()
+        .try_fold(0isize, |sum, p| sum.checked_add(p))
+        .ok_or_else(|| Error::unsolvable(DAY, "the sum of the predictions overflows"))
+}
+
+/// The polynomial through a sequence, in Newton form
+///
+/// Entry `i` of the sequence is the value at `k = i`. The table keeps the
+/// first entry of every row of differences, so
+/// `f(k) = sum(leading[j] * binomial(k, j))`, which also holds for `k`
+/// before the start of the sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Differences {
+    /// First entry of each row, without the rows that are all zeroes
+    leading: Vec<BigInt>,
+}
+
+impl Differences {
+    /// Builds the difference table of `sequence`, which is left as it is
+    pub fn new(sequence: &[isize]) -> Self {
+        let mut row = sequence
+            .iter()
+            .map(|&e| BigInt::from(e))
+            .collect::<Vec<_>>();
+        let mut leading = Vec::new();
+        while row.iter().any(|e| !e.is_zero()) {
+            leading.push(row[0].clone());
+            row = row.windows(2).map(|w| &w[1] - &w[0]).collect();
+        }
+        Self { leading }
+    }
+
+    /// Highest power of `k` in the polynomial, `None` when it is zero
+    pub fn degree(&self) -> Option<usize> {
+        self.leading.len().checked_sub(1)
+    }
+
+    /// First entries of the rows of the table, starting with the sequence
+    pub fn leading(&self) -> &[BigInt] {
+        &self.leading
+    }
+
+    /// Exact value at index `k`
+    pub fn value(&self, k: &BigInt) -> BigInt {
+        let mut binomial = BigInt::one();
+        let mut value = BigInt::zero();
+        for (j, diff) in self.leading.iter().enumerate() {
+            value += diff * &binomial;
+            binomial = binomial * (k - j) / (j + 1);
+        }
+        value
+    }
+
+    /// Value at index `k`, or an error if it does not fit in an `isize`
+    pub fn at(&self, k: isize) -> Result<isize> {
+        let value = self.value(&k.into());
+        isize::try_from(&value)
+            .map_err(|_| Error::unsolvable(DAY, format!("the value {value} at {k} overflows")))
+    }
+
+    /// Coefficients of the powers of `k`, starting with the constant term
+    pub fn coefficients(&self) -> Vec<BigRational> {
+        let mut coefficients = vec![BigRational::zero(); self.leading.len()];
+        // k (k - 1) ... (k - j + 1) / j!, lowest power first
+        let mut basis = vec![BigRational::one()];
+        for (j, diff) in self.leading.iter().enumerate() {
+            for (c, b) in coefficients.iter_mut().zip(&basis) {
+                *c += b * BigRational::from(diff.clone());
+            }
+            let shift = BigRational::from(BigInt::from(j));
+            let divisor = BigRational::from(BigInt::from(j + 1));
+            let mut next = vec![BigRational::zero(); basis.len() + 1];
+            for (i, b) in basis.iter().enumerate() {
+                next[i + 1] += b / &divisor;
+                next[i] -= b * &shift / &divisor;
+            }
+            basis = next;
+        }
+        coefficients
+    }
+}
+
+pub fn predict_lists(
+    lists: &[Vec<isize>],
+    with: impl Fn(&[isize]) -> Result<isize>,
+) -> Result<Vec<isize>> {
+    lists.iter().map(|list| with(list)).collect()
 }
 
-pub fn predict_next(entries: &mut [isize]) -> isize {
-    let (first, derive) = entries.split_first_mut().unwrap();
-    let mut all_zeroes = true;
-    derive.iter_mut().fold(*first, |curr, e| {
-        let next = *e - curr;
-        all_zeroes &= next == 0;
-        mem::replace(e, next)
-    }) + if all_zeroes { 0 } else { predict_next(derive) }
+/// The entry after the end of `entries`
+pub fn predict_next(entries: &[isize]) -> Result<isize> {
+    Differences::new(entries).at(entries.len() as isize)
 }
 
-pub fn predict_prev(entries: &mut [isize]) -> isize {
-    let (last, derive) = entries.split_last_mut().unwrap();
-    let mut all_zeroes = true;
-    derive.iter_mut().rev().fold(*last, |curr, e| {
-        let next = curr - *e;
-        all_zeroes &= next == 0;
-        mem::replace(e, next)
-    }) - if all_zeroes { 0 } else { predict_prev(derive) }
+/// The entry before the start of `entries`
+pub fn predict_prev(entries: &[isize]) -> Result<isize> {
+    Differences::new(entries).at(-1)
 }
 
 #[cfg(test)]
 mod tests {
     use std::path::Path;
 
-    use super::{predict_lists, predict_next, predict_prev, run};
+    use num::{BigInt, BigRational};
+
+    use super::{
+        parse_sequences, predict_lists, predict_next, predict_prev, run, Differences, DAY,
+    };
+    use crate::answers::expected;
+
+    const EXAMPLE: &str = include_str!("../res/day9/example.txt");
 
     #[test]
     fn example1() {
-        let mut lists = run(Path::new("res/day9/example.txt"));
-        let predictions = predict_lists(&mut lists, predict_next);
+        let lists = parse_sequences(EXAMPLE).unwrap();
+        let predictions = predict_lists(&lists, predict_next).unwrap();
         assert_eq!(predictions, vec![18, 28, 68]);
         assert_eq!(predictions.iter().sum::<isize>(), 114);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day9"), ignore = "res/day9/input.txt is missing")]
     fn part1() {
-        let mut lists = run(Path::new("res/day9/input.txt"));
-        let predictions = predict_lists(&mut lists, predict_next);
+        let lists = run(Path::new("res/day9/input.txt")).unwrap();
+        let predictions = predict_lists(&lists, predict_next).unwrap();
         assert_eq!(predictions.len(), 200);
-        assert_eq!(predictions.iter().sum::<isize>(), 1762065988);
+        assert_eq!(
+            predictions.iter().sum::<isize>().to_string(),
+            expected(DAY, 1)
+        );
     }
 
     #[test]
     fn example2() {
-        let mut lists = run(Path::new("res/day9/example.txt"));
-        let predictions = predict_lists(&mut lists, predict_prev);
+        let lists = parse_sequences(EXAMPLE).unwrap();
+        let predictions = predict_lists(&lists, predict_prev).unwrap();
         assert_eq!(predictions, vec![-3, 0, 5]);
     }
 
     #[test]
+    #[cfg_attr(not(input = "day9"), ignore = "res/day9/input.txt is missing")]
     fn part2() {
-        let mut lists = run(Path::new("res/day9/input.txt"));
-        let predictions = predict_lists(&mut lists, predict_prev);
+        let lists = run(Path::new("res/day9/input.txt")).unwrap();
+        let predictions = predict_lists(&lists, predict_prev).unwrap();
         assert_eq!(predictions.len(), 200);
-        assert_eq!(predictions.iter().sum::<isize>(), 1066);
+        assert_eq!(
+            predictions.iter().sum::<isize>().to_string(),
+            expected(DAY, 2)
+        );
+    }
+
+    #[test]
+    fn differences() {
+        let sequence = [10, 13, 16, 21, 30, 45];
+        let table = Differences::new(&sequence);
+        assert_eq!(table.leading(), [10, 3, 0, 2].map(BigInt::from));
+        assert_eq!(table.degree(), Some(3));
+        for (k, &e) in sequence.iter().enumerate() {
+            assert_eq!(table.at(k as isize).unwrap(), e);
+        }
+        assert_eq!(table.at(6).unwrap(), 68);
+        assert_eq!(table.at(-1).unwrap(), 5);
+        assert_eq!(table.at(-2).unwrap(), -4);
+        // 10 + 3k + k(k - 1)(k - 2) / 3
+        let ratio = |n, d| BigRational::new(BigInt::from(n), BigInt::from(d));
+        assert_eq!(
+            table.coefficients(),
+            [ratio(10, 1), ratio(11, 3), ratio(-1, 1), ratio(1, 3)]
+        );
+
+        let zero = Differences::new(&[0, 0, 0]);
+        assert_eq!(zero.degree(), None);
+        assert!(zero.coefficients().is_empty());
+        assert_eq!(zero.at(-5).unwrap(), 0);
+        assert_eq!(Differences::new(&[7]).degree(), Some(0));
+        assert_eq!(Differences::new(&[]).at(3).unwrap(), 0);
+    }
+
+    #[test]
+    fn overflow() {
+        let table = Differences::new(&[isize::MAX - 1, isize::MAX]);
+        assert_eq!(table.at(1).unwrap(), isize::MAX);
+        assert_eq!(
+            table.at(2).unwrap_err().to_string(),
+            format!("day 9: the value {} at 2 overflows", isize::MAX as i128 + 1)
+        );
+        // the differences themselves do not fit in an isize
+        let table = Differences::new(&[isize::MIN, isize::MAX, isize::MIN]);
+        let far = BigInt::from(isize::MIN) - BigInt::from(u64::MAX) * 3;
+        assert_eq!(table.value(&BigInt::from(-1)), far);
+        assert_eq!(table.value(&BigInt::from(3)), far);
+        assert!(table.at(3).is_err());
+        let sequences = vec![vec![isize::MAX - 1, isize::MAX], vec![1]];
+        assert!(predict_lists(&sequences, predict_next).is_err());
     }
 }
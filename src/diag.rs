@@ -0,0 +1,95 @@
+//! Diagnostics of the solvers, written with the [`log`] macros
+//!
+//! Every day logs under its module path, e.g. `adventofcode2023::day10`, so
+//! messages can be filtered per day. While no logger is installed, or the
+//! level is disabled, a log statement costs one atomic load; building with
+//! one of the `max_level_*` features of `log` removes them entirely.
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+const CRATE: &str = concat!(env!("CARGO_CRATE_NAME"), "::");
+
+/// Writes diagnostics to stderr
+pub struct Logger {
+    level: LevelFilter,
+    /// Days to show messages of, all when empty
+    days: Vec<usize>,
+}
+
+impl Logger {
+    pub fn new(level: LevelFilter, days: Vec<usize>) -> Self {
+        Self { level, days }
+    }
+
+    /// Level for the number of `-v` flags: warnings, info, debug, trace
+    pub fn level(verbosity: u8) -> LevelFilter {
+        match verbosity {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+
+    /// Installs the logger, which can only be done once per process
+    pub fn init(self) -> Result<(), log::SetLoggerError> {
+        let level = self.level;
+        log::set_logger(Box::leak(Box::new(self)))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+/// The day a log target belongs to, e.g. 10 for `adventofcode2023::day10`
+pub fn day_of(target: &str) -> Option<usize> {
+    let day = target.strip_prefix(CRATE)?.strip_prefix("day")?;
+    day.split("::").next()?.parse().ok()
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+            && (self.days.is_empty()
+                || day_of(metadata.target()).is_some_and(|day| self.days.contains(&day)))
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let target = record.target();
+            let target = target.strip_prefix(CRATE).unwrap_or(target);
+            eprintln!("[{} {target}] {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use log::{Level, LevelFilter, Log, Metadata};
+
+    use super::{day_of, Logger};
+
+    #[test]
+    fn targets() {
+        assert_eq!(day_of("adventofcode2023::day10"), Some(10));
+        assert_eq!(day_of("adventofcode2023::day12::part2"), Some(12));
+        assert_eq!(day_of("adventofcode2023::util::area"), None);
+        assert_eq!(day_of("adventofcode2023::days"), None);
+    }
+
+    #[test]
+    fn filter() {
+        let logger = Logger::new(LevelFilter::Debug, vec![19]);
+        let enabled = |level, target| {
+            let metadata = Metadata::builder().level(level).target(target).build();
+            logger.enabled(&metadata)
+        };
+        assert!(enabled(Level::Debug, "adventofcode2023::day19"));
+        assert!(!enabled(Level::Trace, "adventofcode2023::day19"));
+        assert!(!enabled(Level::Debug, "adventofcode2023::day10"));
+        assert!(!enabled(Level::Debug, "adventofcode2023::util::area"));
+        assert_eq!(Logger::level(0), LevelFilter::Warn);
+        assert_eq!(Logger::level(7), LevelFilter::Trace);
+    }
+}
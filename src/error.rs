@@ -0,0 +1,138 @@
+use std::{borrow::Cow, fmt, io};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to read the input
+    Io(io::Error),
+    /// The input is malformed
+    Parse(ParseError),
+    /// The input is well-formed, but has no answer
+    Unsolvable {
+        day: usize,
+        reason: Cow<'static, str>,
+    },
+}
+
+impl Error {
+    pub fn unsolvable(day: usize, reason: impl Into<Cow<'static, str>>) -> Self {
+        Self::Unsolvable {
+            day,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => e.fmt(f),
+            Error::Parse(e) => e.fmt(f),
+            Error::Unsolvable { day, reason } => write!(f, "day {day}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Parse(_) | Error::Unsolvable { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// Location and content of malformed input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: usize,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based byte column
+    pub column: usize,
+    /// The offending text
+    pub text: String,
+    /// What the parser was looking for
+    pub expected: String,
+}
+
+impl ParseError {
+    /// Error for `part`, which must be a slice of `input`.
+    ///
+    /// The line and column are computed from the offset of `part` in `input`.
+    pub fn at(day: usize, input: &str, part: &str, expected: impl Into<String>) -> Self {
+        let offset = (part.as_ptr() as usize)
+            .checked_sub(input.as_ptr() as usize)
+            .filter(|ofs| *ofs <= input.len())
+            .unwrap_or(0);
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = offset - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        Self {
+            day,
+            line,
+            column,
+            text: part.to_owned(),
+            expected: expected.into(),
+        }
+    }
+
+    /// Moves the error to line `line` for inputs that are parsed line by line
+    pub fn on_line(mut self, line: usize) -> Self {
+        self.line += line - 1;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {}, line {}, column {}: expected {}, found ",
+            self.day, self.line, self.column, self.expected
+        )?;
+        if self.text.is_empty() {
+            write!(f, "end of input")
+        } else {
+            write!(f, "{:?}", self.text)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::ParseError;
+
+    #[test]
+    fn location() {
+        let text = "abc\ndef ghi\n";
+        let e = ParseError::at(1, text, &text[8..11], "a number");
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 5, "ghi"));
+        assert_eq!(
+            e.to_string(),
+            "day 1, line 2, column 5: expected a number, found \"ghi\""
+        );
+
+        let e = ParseError::at(1, text, &text[text.len()..], "a number");
+        assert_eq!((e.line, e.column), (3, 1));
+        assert!(e.to_string().ends_with("found end of input"));
+
+        let e = ParseError::at(2, "line", "in", "x").on_line(7);
+        assert_eq!((e.line, e.column), (7, 1));
+    }
+}
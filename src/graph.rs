@@ -0,0 +1,126 @@
+//! Exports the graphs of some puzzles to Graphviz DOT or adjacency JSON
+
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::{day20, day8, error::Result};
+
+/// Days whose input can be exported as a graph
+pub const DAYS: [usize; 2] = [8, 20];
+
+/// The graph of the input of `day`, `None` if the day has no graph
+pub fn of_day(day: usize, text: &str) -> Option<Result<Graph>> {
+    match day {
+        8 => Some(
+            day8::parse(text).map(|data| day8::graph(&data, day8::ghost_start, day8::ghost_end)),
+        ),
+        20 => Some(day20::parse(text).map(|circuit| day20::graph(&circuit))),
+        _ => None,
+    }
+}
+
+/// A directed graph with its nodes in output order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Node {
+    pub id: String,
+    /// What the node is, e.g. "start" or "flip-flop"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<&'static str>,
+    /// Graphviz attributes, e.g. `("shape", "box")`
+    #[serde(skip)]
+    pub attrs: Vec<(&'static str, &'static str)>,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Edge {
+    pub to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl Node {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ..Self::default()
+        }
+    }
+}
+
+impl Graph {
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph {} {{\n", quote(name));
+        for node in &self.nodes {
+            let attrs = node
+                .attrs
+                .iter()
+                .map(|(key, value)| format!("{key}={}", quote(value)))
+                .collect::<Vec<_>>();
+            if !attrs.is_empty() {
+                writeln!(dot, "    {} [{}];", quote(&node.id), attrs.join(", ")).unwrap();
+            }
+        }
+        for node in &self.nodes {
+            for edge in &node.edges {
+                write!(dot, "    {} -> {}", quote(&node.id), quote(&edge.to)).unwrap();
+                match &edge.label {
+                    Some(label) => writeln!(dot, " [label={}];", quote(label)).unwrap(),
+                    None => dot.push_str(";\n"),
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The nodes with their kinds and outgoing edges
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("graphs serialize")
+    }
+}
+
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Edge, Graph, Node};
+
+    #[test]
+    fn export() {
+        let graph = Graph {
+            nodes: vec![
+                Node {
+                    kind: Some("start"),
+                    attrs: vec![("shape", "box")],
+                    edges: vec![Edge {
+                        to: "b\"".to_owned(),
+                        label: Some("L".to_owned()),
+                    }],
+                    ..Node::new("a")
+                },
+                Node::new("b\""),
+            ],
+        };
+        assert_eq!(
+            graph.to_dot("g"),
+            "digraph \"g\" {\n    \"a\" [shape=\"box\"];\n    \"a\" -> \"b\\\"\" [label=\"L\"];\n}\n"
+        );
+        let json: serde_json::Value = serde_json::from_str(&graph.to_json()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"nodes": [
+                {"id": "a", "kind": "start", "edges": [{"to": "b\"", "label": "L"}]},
+                {"id": "b\"", "edges": []},
+            ]})
+        );
+    }
+}
@@ -1,3 +1,10 @@
+pub mod answers;
+pub mod bench;
+pub mod diag;
+pub mod error;
+pub mod graph;
+pub mod scaffold;
+pub mod solution;
 pub mod util;
 
 pub mod day1;
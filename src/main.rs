@@ -0,0 +1,302 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+};
+
+use adventofcode2023::{bench, diag::Logger, error::Error, graph, scaffold, solution};
+use argh::FromArgs;
+
+#[derive(FromArgs)]
+/// Advent of Code 2023 solutions
+struct Args {
+    /// show diagnostics of the solvers, repeat for more detail
+    #[argh(switch, short = 'v')]
+    verbose: u8,
+
+    /// only show diagnostics of this day, may be repeated
+    #[argh(option)]
+    log_day: Vec<usize>,
+
+    #[argh(subcommand)]
+    command: Command,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Run(Run),
+    Bench(Bench),
+    NewDay(NewDay),
+    Graph(GraphArgs),
+}
+
+#[derive(FromArgs)]
+/// Run the solver for one part of a day on an input file or stdin
+#[argh(subcommand, name = "run")]
+struct Run {
+    /// day of the puzzle (1-25)
+    #[argh(option, short = 'd')]
+    day: usize,
+
+    /// part of the puzzle (1 or 2)
+    #[argh(option, short = 'p', default = "1")]
+    part: usize,
+
+    /// path to the puzzle input, read from stdin if missing
+    #[argh(positional)]
+    input: Option<PathBuf>,
+}
+
+#[derive(FromArgs)]
+/// Time parsing and both parts on the puzzle inputs
+#[argh(subcommand, name = "bench")]
+struct Bench {
+    /// day to benchmark, may be repeated (default: every day with an input)
+    #[argh(option, short = 'd')]
+    day: Vec<usize>,
+
+    /// untimed runs before measuring
+    #[argh(option, default = "3")]
+    warmup: usize,
+
+    /// timed runs per stage
+    #[argh(option, default = "10")]
+    runs: usize,
+
+    /// directory with the dayN/input.txt files
+    #[argh(option, default = "PathBuf::from(\"res\")")]
+    res: PathBuf,
+
+    /// write the measurements as JSON to this file
+    #[argh(option)]
+    json: Option<PathBuf>,
+
+    /// JSON file of an earlier run to compare against
+    #[argh(option)]
+    baseline: Option<PathBuf>,
+}
+
+#[derive(FromArgs)]
+/// Create and register the module of a new day from src/template.rs
+#[argh(subcommand, name = "new-day")]
+struct NewDay {
+    /// day of the puzzle (1-25)
+    #[argh(positional)]
+    day: usize,
+
+    /// root of the repository
+    #[argh(option, default = "PathBuf::from(\".\")")]
+    root: PathBuf,
+}
+
+#[derive(FromArgs)]
+/// Print the graph of a puzzle input (days 8 and 20) from a file or stdin
+#[argh(subcommand, name = "graph")]
+struct GraphArgs {
+    /// day of the puzzle
+    #[argh(option, short = 'd')]
+    day: usize,
+
+    /// output format, dot or json
+    #[argh(option, default = "Format::Dot")]
+    format: Format,
+
+    /// path to the puzzle input, read from stdin if missing
+    #[argh(positional)]
+    input: Option<PathBuf>,
+}
+
+enum Format {
+    Dot,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(Format::Dot),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format {s}, expected dot or json")),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Args = argh::from_env();
+    let logger = Logger::new(Logger::level(args.verbose), args.log_day);
+    logger.init().expect("no other logger is installed");
+    match args.command {
+        Command::Run(run) => match solve(run.day, run.part, run.input.as_deref()) {
+            Ok(answer) => {
+                println!("{answer}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        },
+        Command::Bench(args) => match run_bench(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        },
+        Command::NewDay(args) => match new_day(args.day, &args.root) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        },
+        Command::Graph(args) => match export_graph(&args) {
+            Ok(text) => {
+                print!("{text}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
+
+fn solve(day: usize, part: usize, path: Option<&Path>) -> Result<String, String> {
+    let solver = solution::get(day)
+        .ok_or_else(|| format!("no solver for day {day}"))?
+        .solver;
+    if !matches!(part, 1 | 2) {
+        return Err(format!("invalid puzzle part {part}"));
+    }
+    let (input, name) = match path {
+        Some(path) => {
+            let name = path.display().to_string();
+            let file = File::open(path).map_err(|e| format!("failed to read {name}: {e}"))?;
+            (solver.read(&mut BufReader::new(file)), name)
+        }
+        None => (solver.read(&mut io::stdin().lock()), "stdin".to_owned()),
+    };
+    let input = input.map_err(|e| match e {
+        Error::Io(e) => format!("failed to read {name}: {e}"),
+        e => e.to_string(),
+    })?;
+    match part {
+        1 => input.part1(),
+        _ => input.part2(),
+    }
+    .map_err(|e| e.to_string())
+}
+
+fn export_graph(args: &GraphArgs) -> Result<String, String> {
+    if !graph::DAYS.contains(&args.day) {
+        return Err(format!("no graph for day {}", args.day));
+    }
+    let mut text = String::new();
+    match &args.input {
+        Some(path) => File::open(path).and_then(|mut file| file.read_to_string(&mut text)),
+        None => io::stdin().read_to_string(&mut text),
+    }
+    .map_err(|e| match &args.input {
+        Some(path) => format!("failed to read {}: {e}", path.display()),
+        None => format!("failed to read stdin: {e}"),
+    })?;
+    let graph = graph::of_day(args.day, &text)
+        .expect("checked above")
+        .map_err(|e| e.to_string())?;
+    Ok(match args.format {
+        Format::Dot => graph.to_dot(&format!("day{}", args.day)),
+        Format::Json => graph.to_json() + "\n",
+    })
+}
+
+fn run_bench(args: &Bench) -> Result<(), String> {
+    let days = if args.day.is_empty() {
+        solution::DAYS.iter().collect::<Vec<_>>()
+    } else {
+        let days = args
+            .day
+            .iter()
+            .map(|&day| solution::get(day).ok_or_else(|| format!("no solver for day {day}")));
+        days.collect::<Result<_, _>>()?
+    };
+    let baseline = match &args.baseline {
+        Some(path) => {
+            let json = std::fs::read_to_string(path)
+                .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+            Some(bench::from_json(&json).map_err(|e| format!("{}: {e}", path.display()))?)
+        }
+        None => None,
+    };
+
+    let config = bench::Config {
+        warmup: args.warmup,
+        runs: args.runs,
+    };
+    let mut results = vec![];
+    for day in days {
+        let path = args.res.join(format!("day{}/input.txt", day.day));
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            eprintln!("skipping day {}: no input at {}", day.day, path.display());
+            continue;
+        };
+        let measured = bench::bench_day(day, &text, &config)
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        results.extend(measured);
+    }
+
+    print!("{}", bench::table(&results, baseline.as_deref()));
+    if let Some(path) = &args.json {
+        std::fs::write(path, bench::to_json(&results))
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn new_day(day: usize, root: &Path) -> Result<(), String> {
+    if !(1..=25).contains(&day) {
+        return Err(format!("invalid puzzle day {day}"));
+    }
+    let read = |path: &Path| {
+        std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {e}", path.display()))
+    };
+    let write = |path: &Path, text: &str| {
+        std::fs::write(path, text).map_err(|e| format!("failed to write {}: {e}", path.display()))
+    };
+
+    let module_path = root.join(format!("src/day{day}.rs"));
+    if module_path.exists() {
+        return Err(format!("{} already exists", module_path.display()));
+    }
+    let lib_path = root.join("src/lib.rs");
+    let registry_path = root.join("src/solution.rs");
+    let manifest_path = root.join("answers.toml");
+    let module = scaffold::module(&read(&root.join("src/template.rs"))?, day);
+    let lib = scaffold::register_module(&read(&lib_path)?, day)?;
+    let registry = scaffold::register_solver(&read(&registry_path)?, day)?;
+    let manifest = read(&manifest_path)? + &scaffold::manifest_entries(day);
+
+    write(&module_path, &module)?;
+    write(&lib_path, &lib)?;
+    write(&registry_path, &registry)?;
+    write(&manifest_path, &manifest)?;
+    let res = root.join(format!("res/day{day}"));
+    std::fs::create_dir_all(&res)
+        .map_err(|e| format!("failed to create {}: {e}", res.display()))?;
+    let example = res.join("example.txt");
+    if !example.exists() {
+        write(&example, "")?;
+    }
+    println!(
+        "created {} and {}",
+        module_path.display(),
+        example.display()
+    );
+    Ok(())
+}
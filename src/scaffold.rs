@@ -0,0 +1,111 @@
+//! Generates the module of a new day from `src/template.rs` and registers it
+
+use std::cmp::Ordering;
+
+/// The template with its `X` placeholders replaced by `day`
+pub fn module(template: &str, day: usize) -> String {
+    template
+        .replace("DAY: usize = X;", &format!("DAY: usize = {day};"))
+        .replace("DayX", &format!("Day{day}"))
+        .replace("dayX", &format!("day{day}"))
+}
+
+/// Adds `pub mod dayN;` to `lib.rs`, next to the other days
+pub fn register_module(lib: &str, day: usize) -> Result<String, String> {
+    let line = format!("pub mod day{day};");
+    insert_sorted(lib, &line, day, |line| {
+        line.strip_prefix("pub mod day")?
+            .strip_suffix(';')?
+            .parse()
+            .ok()
+    })
+    .ok_or_else(|| format!("could not add day {day} to lib.rs"))
+}
+
+/// Adds the solution of `day` to the [`DAYS`](crate::solution::DAYS) registry
+pub fn register_solver(solution: &str, day: usize) -> Result<String, String> {
+    let line = format!("    Day::new({day}, &day{day}::Day{day}),");
+    insert_sorted(solution, &line, day, |line| {
+        line.trim()
+            .strip_prefix("Day::new(")?
+            .split(',')
+            .next()?
+            .parse()
+            .ok()
+    })
+    .ok_or_else(|| format!("could not add day {day} to the registry"))
+}
+
+/// Entries without answers for the example and input of `day`
+pub fn manifest_entries(day: usize) -> String {
+    ["example", "input"]
+        .map(|file| format!("\n[[input]]\nday = {day}\nfile = \"day{day}/{file}.txt\"\n"))
+        .concat()
+}
+
+/// Inserts `new` after the last line of an earlier day
+///
+/// Returns `None` if `day` is already there or `text` has no lines of days.
+fn insert_sorted(
+    text: &str,
+    new: &str,
+    day: usize,
+    day_of: impl Fn(&str) -> Option<usize>,
+) -> Option<String> {
+    let mut lines = text.lines().collect::<Vec<_>>();
+    let mut at = None;
+    for (i, line_day) in lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, l)| Some((i, day_of(l)?)))
+    {
+        match line_day.cmp(&day) {
+            Ordering::Less => at = Some(i + 1),
+            Ordering::Equal => return None,
+            Ordering::Greater => at = at.or(Some(i)),
+        }
+    }
+    lines.insert(at?, new);
+    Some(lines.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::answers::Manifest;
+
+    #[test]
+    fn module() {
+        let module = super::module(include_str!("template.rs"), 21);
+        assert!(module.contains("const DAY: usize = 21;"));
+        assert!(module.contains("impl Solution for Day21 {"));
+        assert!(module.contains("include_str!(\"../res/day21/example.txt\")"));
+        assert!(!module.contains("dayX") && !module.contains("DayX"));
+    }
+
+    #[test]
+    fn register() {
+        let lib = "pub mod util;\n\npub mod day1;\npub mod day2;\n\npub mod day10;\n";
+        assert_eq!(
+            super::register_module(lib, 3).unwrap(),
+            "pub mod util;\n\npub mod day1;\npub mod day2;\npub mod day3;\n\npub mod day10;\n"
+        );
+        assert!(super::register_module(lib, 2).is_err());
+
+        let registry = "pub static DAYS: &[Day] = &[\n    Day::new(1, &day1::Day1),\n    \
+                        Day::new(20, &day20::Day20),\n];\n";
+        let registry = super::register_solver(registry, 21).unwrap();
+        assert!(registry
+            .contains("    Day::new(20, &day20::Day20),\n    Day::new(21, &day21::Day21),\n];"));
+        assert!(super::register_solver(&registry, 21).is_err());
+        assert!(super::register_solver(&registry, 2)
+            .unwrap()
+            .contains("Day::new(1, &day1::Day1),\n    Day::new(2, &day2::Day2),\n"));
+    }
+
+    #[test]
+    fn manifest() {
+        let manifest = Manifest::parse(&super::manifest_entries(21)).unwrap();
+        assert_eq!(manifest.for_day(21).count(), 2);
+        assert!(manifest.inputs.iter().all(|e| e.part1.is_none()));
+    }
+}
@@ -0,0 +1,155 @@
+use std::{fmt::Display, io::Read};
+
+use crate::{error::Result, *};
+
+/// A named alternative implementation of one of the parts
+pub type Variant<I> = (&'static str, fn(&I) -> Result<String>);
+
+/// A puzzle solution: parse the input once, then solve both parts on it.
+pub trait Solution {
+    type Input;
+    type Answer1: Display;
+    type Answer2: Display;
+
+    fn parse(text: &str) -> Result<Self::Input>;
+
+    /// Reads the whole input from `reader`, e.g. stdin, and parses it
+    fn read(mut reader: impl Read) -> Result<Self::Input> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Self::parse(&text)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Self::Answer1>;
+    fn part2(input: &Self::Input) -> Result<Self::Answer2>;
+
+    /// Other implementations of the parts, to compare them in benchmarks
+    fn variants() -> Vec<Variant<Self::Input>> {
+        vec![]
+    }
+}
+
+/// Object-safe view of a [`Solution`], used by the [`DAYS`] registry.
+pub trait Solver: Sync {
+    fn parse(&self, text: &str) -> Result<Box<dyn Parsed>>;
+    fn read(&self, reader: &mut dyn Read) -> Result<Box<dyn Parsed>>;
+}
+
+/// A solver bound to a parsed input, see [`Parsed::variants`]
+pub type BoundVariant<'a> = (&'static str, Box<dyn Fn() -> Result<String> + 'a>);
+
+/// A parsed puzzle input with the answers rendered as strings.
+pub trait Parsed {
+    fn part1(&self) -> Result<String>;
+    fn part2(&self) -> Result<String>;
+    fn variants(&self) -> Vec<BoundVariant<'_>>;
+}
+
+struct Input<S: Solution>(S::Input);
+
+impl<S: Solution> Parsed for Input<S> {
+    fn part1(&self) -> Result<String> {
+        S::part1(&self.0).map(|a| a.to_string())
+    }
+
+    fn part2(&self) -> Result<String> {
+        S::part2(&self.0).map(|a| a.to_string())
+    }
+
+    fn variants(&self) -> Vec<BoundVariant<'_>> {
+        S::variants()
+            .into_iter()
+            .map(|(name, f)| (name, Box::new(move || f(&self.0)) as Box<_>))
+            .collect()
+    }
+}
+
+impl<S> Solver for S
+where
+    S: Solution + Sync + 'static,
+{
+    fn parse(&self, text: &str) -> Result<Box<dyn Parsed>> {
+        Ok(Box::new(Input::<S>(S::parse(text)?)))
+    }
+
+    fn read(&self, reader: &mut dyn Read) -> Result<Box<dyn Parsed>> {
+        Ok(Box::new(Input::<S>(S::read(reader)?)))
+    }
+}
+
+pub struct Day {
+    pub day: usize,
+    pub solver: &'static dyn Solver,
+}
+
+impl Day {
+    const fn new(day: usize, solver: &'static dyn Solver) -> Self {
+        Self { day, solver }
+    }
+}
+
+/// All solutions, ordered by day
+pub static DAYS: &[Day] = &[
+    Day::new(1, &day1::Day1),
+    Day::new(2, &day2::Day2),
+    Day::new(3, &day3::Day3),
+    Day::new(4, &day4::Day4),
+    Day::new(5, &day5::Day5),
+    Day::new(6, &day6::Day6),
+    Day::new(7, &day7::Day7),
+    Day::new(8, &day8::Day8),
+    Day::new(9, &day9::Day9),
+    Day::new(10, &day10::Day10),
+    Day::new(11, &day11::Day11),
+    Day::new(12, &day12::Day12),
+    Day::new(13, &day13::Day13),
+    Day::new(14, &day14::Day14),
+    Day::new(15, &day15::Day15),
+    Day::new(16, &day16::Day16),
+    Day::new(17, &day17::Day17),
+    Day::new(18, &day18::Day18),
+    Day::new(19, &day19::Day19),
+    Day::new(20, &day20::Day20),
+];
+
+pub fn get(day: usize) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.day == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DAYS;
+    use crate::error::Error;
+
+    #[test]
+    fn registry() {
+        let days = DAYS.iter().map(|d| d.day).collect::<Vec<_>>();
+        assert_eq!(days[..20], (1..=20).collect::<Vec<_>>());
+        assert!(days.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn generic() {
+        let day6 = super::get(6).unwrap();
+        let input = day6
+            .solver
+            .parse("Time:      7  15   30\nDistance:  9  40  200\n")
+            .unwrap();
+        assert_eq!(input.part1().unwrap(), "288");
+        assert_eq!(input.part2().unwrap(), "71503");
+        assert!(day6.solver.parse("Time: 7\n").is_err());
+    }
+
+    #[test]
+    fn read() {
+        let day2 = super::get(2).unwrap();
+        let mut reader = "Game 1: 3 blue, 4 red; 1 red, 2 green\n".as_bytes();
+        let input = day2.solver.read(&mut reader).unwrap();
+        assert_eq!(input.part1().unwrap(), "1");
+        assert_eq!(input.part2().unwrap(), "24");
+
+        let mut invalid = &[b'T', 0xff][..];
+        let e = day2.solver.read(&mut invalid).err().unwrap();
+        assert!(matches!(e, Error::Io(_)), "{e}");
+    }
+}
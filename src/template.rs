@@ -1,32 +1,71 @@
 use std::path::Path;
 
-pub fn run(path: &Path) {
-    let _text = std::fs::read_to_string(path).unwrap();
+use crate::{
+    error::{Error, Result},
+    solution::Solution,
+};
+
+const DAY: usize = X;
+
+pub struct DayX;
+
+impl Solution for DayX {
+    type Input = Vec<String>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(text: &str) -> Result<Self::Input> {
+        Ok(text.lines().map(str::to_owned).collect())
+    }
+
+    fn part1(_input: &Self::Input) -> Result<usize> {
+        Err(Error::unsolvable(DAY, "not solved yet"))
+    }
+
+    fn part2(_input: &Self::Input) -> Result<usize> {
+        Err(Error::unsolvable(DAY, "not solved yet"))
+    }
+}
+
+pub fn run(path: &Path) -> Result<(usize, usize)> {
+    let input = DayX::parse(&std::fs::read_to_string(path)?)?;
+    Ok((DayX::part1(&input)?, DayX::part2(&input)?))
 }
 
 #[cfg(test)]
 mod tests {
     use std::path::Path;
 
-    use super::run;
+    use super::{run, DayX, DAY};
+    use crate::{answers::expected, solution::Solution};
+
+    const EXAMPLE: &str = include_str!("../res/dayX/example.txt");
 
     #[test]
+    #[ignore = "not solved yet"]
     fn example1() {
-        run(Path::new("res/dayX/example.txt"));
+        let input = DayX::parse(EXAMPLE).unwrap();
+        assert_eq!(DayX::part1(&input).unwrap(), 0);
     }
-    
+
     #[test]
+    #[ignore = "not solved yet"]
     fn part1() {
-        run(Path::new("res/dayX/input.txt"));
+        let (part1, _) = run(Path::new("res/dayX/input.txt")).unwrap();
+        assert_eq!(part1.to_string(), expected(DAY, 1));
     }
 
     #[test]
+    #[ignore = "not solved yet"]
     fn example2() {
-        run(Path::new("res/dayX/example.txt"));
+        let input = DayX::parse(EXAMPLE).unwrap();
+        assert_eq!(DayX::part2(&input).unwrap(), 0);
     }
 
     #[test]
+    #[ignore = "not solved yet"]
     fn part2() {
-        run(Path::new("res/dayX/input.txt"));
+        let (_, part2) = run(Path::new("res/dayX/input.txt")).unwrap();
+        assert_eq!(part2.to_string(), expected(DAY, 2));
     }
-}
\ No newline at end of file
+}
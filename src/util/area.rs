@@ -1,8 +1,10 @@
 use std::{collections::BTreeMap, fmt::Display, ops::Range, time::SystemTime};
 
+use log::trace;
+
 use super::{Direction, Pos};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Edge {
     // `F`
     SouthEast,
@@ -34,7 +36,32 @@ impl Edge {
         }
     }
 
-    fn to_box_drawing_char(self) -> char {
+    /// The edge drawn by a pipe of the puzzle map, e.g. `F`
+    pub fn of_pipe(pipe: char) -> Option<Self> {
+        match pipe {
+            'F' => Some(Self::SouthEast),
+            'L' => Some(Self::NorthEast),
+            '7' => Some(Self::SouthWest),
+            'J' => Some(Self::NorthWest),
+            '|' => Some(Self::NorthSouth),
+            '-' => Some(Self::EastWest),
+            _ => None,
+        }
+    }
+
+    /// The pipe of the puzzle map drawing this edge, the inverse of [`Edge::of_pipe`]
+    pub fn to_pipe(self) -> char {
+        match self {
+            Self::SouthEast => 'F',
+            Self::NorthEast => 'L',
+            Self::SouthWest => '7',
+            Self::NorthWest => 'J',
+            Self::NorthSouth => '|',
+            Self::EastWest => '-',
+        }
+    }
+
+    pub fn to_box_drawing_char(self) -> char {
         match self {
             Self::SouthEast => '╔',
             Self::NorthEast => '╚',
@@ -59,8 +86,8 @@ enum State {
 #[allow(unused_variables)]
 pub trait AreaListener<A> {
     fn on_edge(&mut self, xy: Pos<A>, edge: Edge) {}
-    fn on_inside(&mut self) {}
-    fn on_outside(&mut self) {}
+    fn on_inside(&mut self, xy: Pos<A>) {}
+    fn on_outside(&mut self, xy: Pos<A>) {}
     fn on_newline(&mut self) {}
     fn on_done(&mut self) {}
 }
@@ -70,7 +97,8 @@ pub struct Noop;
 impl<A> AreaListener<A> for Noop {}
 
 pub struct Drawing {
-    path: String,
+    /// Where to write the drawing when done, `None` to keep it in memory
+    path: Option<String>,
     content: String,
 }
 
@@ -81,23 +109,34 @@ impl Drawing {
             .unwrap();
         let path = format!("res/day{}/output{}.txt", day, ts.as_micros());
         Self {
-            path,
+            path: Some(path),
             content: String::new(),
         }
     }
+
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            content: String::new(),
+        }
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
 }
 
 impl<A: Display> AreaListener<A> for Drawing {
     fn on_edge(&mut self, (x, y): Pos<A>, edge: Edge) {
-        eprintln!("{edge:?} {x},{y}");
+        trace!("{edge:?} {x},{y}");
         self.content.push(edge.to_box_drawing_char());
     }
 
-    fn on_inside(&mut self) {
+    fn on_inside(&mut self, _: Pos<A>) {
         self.content.push('i');
     }
 
-    fn on_outside(&mut self) {
+    fn on_outside(&mut self, _: Pos<A>) {
         self.content.push(' ');
     }
 
@@ -106,7 +145,9 @@ impl<A: Display> AreaListener<A> for Drawing {
     }
 
     fn on_done(&mut self) {
-        std::fs::write(&self.path, &self.content).unwrap();
+        if let Some(path) = &self.path {
+            std::fs::write(path, &self.content).unwrap();
+        }
     }
 }
 
@@ -148,10 +189,10 @@ where
                     (BottomBorder, EastWest) => BottomBorder,
                 };
             } else if state == In {
-                listener.on_inside();
+                listener.on_inside((x, y));
                 in_fields += 1;
             } else {
-                listener.on_outside();
+                listener.on_outside((x, y));
             }
         }
         listener.on_newline();
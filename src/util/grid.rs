@@ -0,0 +1,282 @@
+use std::ops::{Index, IndexMut};
+
+use crate::error::ParseError;
+
+use super::{rows, MapDimensions, Pos};
+
+/// A rectangular grid of cells, stored row by row
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert!(width > 0, "a grid needs at least one column");
+        assert_eq!(cells.len(), width * height);
+        Self {
+            cells,
+            width,
+            height,
+        }
+    }
+
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Pos<usize>) -> T) -> Self {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(&mut f)
+            .collect();
+        Self::new(width, height, cells)
+    }
+
+    /// Parses a map of lines with one ASCII character per cell, see [`MapDimensions::check`]
+    ///
+    /// Characters for which `cell` returns `None` are reported as `expected`.
+    pub fn parse(
+        day: usize,
+        text: &str,
+        expected: &str,
+        mut cell: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let dim = MapDimensions::check(day, text)?;
+        let mut cells = Vec::with_capacity(dim.width() * dim.height());
+        for row in rows(text) {
+            for (i, c) in row.char_indices() {
+                match cell(c).filter(|_| c.is_ascii()) {
+                    Some(value) => cells.push(value),
+                    None => {
+                        let part = &row[i..i + c.len_utf8()];
+                        return Err(ParseError::at(day, text, part, expected));
+                    }
+                }
+            }
+        }
+        Ok(Self::new(dim.width(), dim.height(), cells))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Dimensions for navigating the grid with a [`Direction`](super::Direction)
+    pub fn dim(&self) -> MapDimensions {
+        MapDimensions::new(self.width, self.height)
+    }
+
+    pub fn contains(&self, (x, y): Pos<usize>) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, pos: Pos<usize>) -> Option<&T> {
+        self.contains(pos).then(|| &self[pos])
+    }
+
+    pub fn get_mut(&mut self, pos: Pos<usize>) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self[pos])
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> + ExactSizeIterator {
+        self.cells.chunks(self.width)
+    }
+
+    pub fn col(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        assert!(x < self.width);
+        self.cells[x..].iter().step_by(self.width)
+    }
+
+    pub fn cols(&self) -> impl DoubleEndedIterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.col(x))
+    }
+
+    /// All positions, row by row
+    pub fn positions(&self) -> impl Iterator<Item = Pos<usize>> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// All cells with their positions, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Pos<usize>, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    /// Neighbors to the north, west, east and south that are inside the grid
+    pub fn neighbors4(&self, (x, y): Pos<usize>) -> impl Iterator<Item = Pos<usize>> + '_ {
+        const OFFSETS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+        self.offsets((x, y), &OFFSETS)
+    }
+
+    /// Neighbors including the diagonals that are inside the grid
+    pub fn neighbors8(&self, (x, y): Pos<usize>) -> impl Iterator<Item = Pos<usize>> + '_ {
+        const OFFSETS: [(isize, isize); 8] = [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ];
+        self.offsets((x, y), &OFFSETS)
+    }
+
+    fn offsets<'a>(
+        &'a self,
+        (x, y): Pos<usize>,
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = Pos<usize>> + 'a {
+        offsets.iter().filter_map(move |&(dx, dy)| {
+            let pos = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+            self.contains(pos).then_some(pos)
+        })
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid::new(self.width, self.height, self.cells.iter().map(f).collect())
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    /// Mirrors the grid along its main diagonal
+    pub fn transpose(&self) -> Self {
+        Self::from_fn(self.height, self.width, |(x, y)| self[(y, x)].clone())
+    }
+
+    /// Rotates the grid by 90 degrees clockwise
+    pub fn rotate_cw(&self) -> Self {
+        let h = self.height;
+        Self::from_fn(h, self.width, |(x, y)| self[(y, h - 1 - x)].clone())
+    }
+
+    /// Rotates the grid by 90 degrees counter-clockwise
+    pub fn rotate_ccw(&self) -> Self {
+        let w = self.width;
+        Self::from_fn(self.height, w, |(x, y)| self[(w - 1 - y, x)].clone())
+    }
+}
+
+impl<T> Index<Pos<usize>> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): Pos<usize>) -> &T {
+        assert!(x < self.width, "x={x} out of bounds");
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<Pos<usize>> for Grid<T> {
+    fn index_mut(&mut self, (x, y): Pos<usize>) -> &mut T {
+        assert!(x < self.width, "x={x} out of bounds");
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Grid;
+
+    fn grid() -> Grid<char> {
+        Grid::parse(0, "abc\ndef\n", "a letter", Some).unwrap()
+    }
+
+    #[test]
+    fn rows_and_cols() {
+        let g = grid();
+        assert_eq!((g.width(), g.height()), (3, 2));
+        assert_eq!(g[(2, 1)], 'f');
+        assert_eq!(g.get((3, 0)), None);
+        assert_eq!(
+            g.rows().collect::<Vec<_>>(),
+            [['a', 'b', 'c'], ['d', 'e', 'f']]
+        );
+        let cols = g
+            .cols()
+            .map(|col| col.collect::<String>())
+            .collect::<Vec<_>>();
+        assert_eq!(cols, ["ad", "be", "cf"]);
+    }
+
+    #[test]
+    fn transform() {
+        let g = grid();
+        let rows = |g: &Grid<char>| {
+            g.rows()
+                .map(|r| r.iter().collect::<String>())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rows(&g.transpose()), ["ad", "be", "cf"]);
+        assert_eq!(rows(&g.rotate_cw()), ["da", "eb", "fc"]);
+        assert_eq!(rows(&g.rotate_ccw()), ["cf", "be", "ad"]);
+        assert_eq!(g.rotate_cw().rotate_ccw(), g);
+    }
+
+    #[test]
+    fn neighbors() {
+        let g = grid();
+        let n4 = g.neighbors4((0, 0)).collect::<Vec<_>>();
+        assert_eq!(n4, [(1, 0), (0, 1)]);
+        let n8 = g.neighbors8((1, 1)).collect::<Vec<_>>();
+        assert_eq!(n8, [(0, 0), (1, 0), (2, 0), (0, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn parse_error() {
+        let e = Grid::parse(7, "..\n.x\n", "'.'", |c| (c == '.').then_some(())).unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 2, "x"));
+        let e = Grid::parse(7, "\n", "'.'", |c| (c == '.').then_some(())).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 7, line 1, column 1: expected a tile, found \"\\n\""
+        );
+        let e = Grid::parse(7, "", "'.'", |c| (c == '.').then_some(())).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 7, line 1, column 1: expected a tile, found end of input"
+        );
+    }
+
+    #[test]
+    fn without_last_newline() {
+        assert_eq!(
+            Grid::parse(0, "abc\ndef", "a letter", Some).unwrap(),
+            grid()
+        );
+        let e = Grid::parse(0, "abc\nde", "a letter", Some).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 0, line 2, column 1: expected a line of width 3, found \"de\""
+        );
+    }
+
+    #[test]
+    fn crlf() {
+        assert_eq!(
+            Grid::parse(0, "abc\r\ndef\r\n", "a letter", Some).unwrap(),
+            grid()
+        );
+        assert_eq!(
+            Grid::parse(0, "abc\r\ndef", "a letter", Some).unwrap(),
+            grid()
+        );
+        // only line breaks may contain a '\r'
+        let e = Grid::parse(0, "a\rc\ndef\n", "a letter", |c| {
+            c.is_alphabetic().then_some(c)
+        })
+        .unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (1, 2, "\r"));
+    }
+}
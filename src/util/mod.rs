@@ -1,11 +1,13 @@
-use std::{
-    ops::{Deref, Range},
-    path::Path,
-};
+use std::ops::Range;
 
 use num::Integer;
 
+use crate::error::ParseError;
+
 pub mod area;
+pub mod grid;
+
+pub use grid::Grid;
 
 pub type Pos<A> = (A, A);
 
@@ -27,6 +29,14 @@ impl Direction {
     }
 }
 
+/// The lines of a map without their line breaks, `\n` or `\r\n`
+pub fn rows(map: &str) -> impl Iterator<Item = &str> {
+    map.split_inclusive('\n').map(|line| {
+        let row = line.strip_suffix('\n').unwrap_or(line);
+        row.strip_suffix('\r').unwrap_or(row)
+    })
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MapDimensions {
     size: (usize, usize),
@@ -52,6 +62,44 @@ impl MapDimensions {
         }
     }
 
+    /// Checks that `map` is a rectangle of non-empty lines
+    ///
+    /// Lines may end in `\r\n`, and the last one may lack its line break.
+    pub fn check(day: usize, map: &str) -> Result<Self, ParseError> {
+        let mut rows = rows(map);
+        let width = rows.next().map_or(0, str::len);
+        if width == 0 {
+            let found = map.get(..1).unwrap_or(map);
+            return Err(ParseError::at(day, map, found, "a tile"));
+        }
+        let mut height = 1;
+        for row in rows {
+            if row.len() != width {
+                let expected = format!("a line of width {width}");
+                return Err(ParseError::at(day, map, row, expected));
+            }
+            height += 1;
+        }
+        Ok(Self::new(width, height))
+    }
+
+    /// Like [`check`](Self::check), but also checks that every tile is one of `tiles`
+    pub fn check_tiles(
+        day: usize,
+        map: &str,
+        tiles: &str,
+        expected: &str,
+    ) -> Result<Self, ParseError> {
+        let dim = Self::check(day, map)?;
+        for row in rows(map) {
+            if let Some((pos, c)) = row.char_indices().find(|&(_, c)| !tiles.contains(c)) {
+                let tile = &row[pos..pos + c.len_utf8()];
+                return Err(ParseError::at(day, map, tile, expected));
+            }
+        }
+        Ok(dim)
+    }
+
     pub fn index(&self, (x, y): (usize, usize)) -> usize {
         y * self.stride() + x
     }
@@ -148,39 +196,3 @@ impl MapDimensions {
         }
     }
 }
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Map {
-    inner: String,
-    dim: MapDimensions,
-}
-
-impl Map {
-    pub fn open(path: &Path) -> std::io::Result<Self> {
-        let text = std::fs::read_to_string(path)?;
-        Ok(Self::of(text))
-    }
-
-    pub fn of(inner: String) -> Self {
-        Self {
-            dim: MapDimensions::of(&inner),
-            inner,
-        }
-    }
-
-    pub fn dim(&self) -> &MapDimensions {
-        &self.dim
-    }
-
-    pub(crate) fn ascii_num_at(&self, (x, y): (usize, usize)) -> u8 {
-        self.as_bytes()[self.dim.index((x, y))] - b'0'
-    }
-}
-
-impl Deref for Map {
-    type Target = String;
-
-    fn deref(&self) -> &Self::Target {
-        &self.inner
-    }
-}